@@ -0,0 +1,115 @@
+// micro-benchmarks for the search, the crate is a binary so the engine's modules are compiled into the benchmark
+#![allow(dead_code, unused_imports, unused_macros)]
+
+#[macro_use]
+extern crate lazy_static;
+
+// same modules and imports as main.rs, the engine code refers to items through them
+#[macro_use]
+#[path = "../src/uci/mod.rs"]
+mod uci;
+use uci::*;
+
+#[path = "../src/board/mod.rs"]
+mod board;
+use board::*;
+
+#[path = "../src/move/mod.rs"]
+mod r#move;
+use r#move::*;
+
+#[path = "../src/search/mod.rs"]
+mod search;
+use search::*;
+
+#[path = "../src/evaluation/mod.rs"]
+mod evaluation;
+use evaluation::*;
+
+#[path = "../src/cache/mod.rs"]
+mod cache;
+use cache::*;
+
+#[path = "../src/tools/mod.rs"]
+mod tools;
+
+use board::position::*;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SEARCH_DEPTH: u8 = 6;
+
+// search every bench position to a fixed depth with a fresh TT, returns the nodes searched
+fn search_bench_positions(depth: u8) -> u64 {
+    let mut nodes = 0;
+    for fen in BENCH_POSITIONS.iter() {
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        // a clock that never runs out, so polling it is part of the measurement like in a real game
+        searcher.time_manager.movetime = i32::MAX;
+        searcher.time_manager.init(0, false);
+
+        unsafe { TT.reset(); }
+        searcher.negamax(&mut position, -INFINITY, INFINITY, depth, true, false);
+        nodes += searcher.nodes;
+    }
+    return nodes;
+}
+
+// what checking the clock costs per node, the old search read the system time on every node
+fn time_polling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("time polling");
+    group.throughput(Throughput::Elements(CHECK_TIME_NODES));
+
+    group.bench_function("system time every node", |b| {
+        let stoptime = u128::MAX;
+        b.iter(|| {
+            let mut stop = false;
+            for _ in 0..CHECK_TIME_NODES {
+                stop |= SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() > stoptime;
+            }
+            return stop;
+        });
+    });
+
+    let mut searcher = Searcher::new();
+    searcher.time_manager.movetime = i32::MAX;
+    searcher.time_manager.init(0, false);
+    group.bench_function("instant every node", |b| {
+        b.iter(|| {
+            let mut stop = false;
+            for _ in 0..CHECK_TIME_NODES {
+                stop |= searcher.time_manager.hard_stop();
+            }
+            return stop;
+        });
+    });
+
+    group.bench_function(format!("instant every {} nodes", CHECK_TIME_NODES), |b| {
+        b.iter(|| {
+            let mut stop = false;
+            for _ in 0..CHECK_TIME_NODES {
+                searcher.nodes += 1;
+                stop |= searcher.stop_search();
+            }
+            return stop;
+        });
+    });
+    group.finish();
+}
+
+// nodes per second of a fixed depth search over the bench positions
+fn search_speed(c: &mut Criterion) {
+    init_all();
+    let nodes = search_bench_positions(SEARCH_DEPTH);
+
+    let mut group = c.benchmark_group("search");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(nodes));
+    group.bench_function(format!("bench positions depth {}", SEARCH_DEPTH), |b| b.iter(|| search_bench_positions(SEARCH_DEPTH)));
+    group.finish();
+}
+
+criterion_group!(benches, time_polling, search_speed);
+criterion_main!(benches);
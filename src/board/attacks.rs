@@ -5,6 +5,7 @@ use crate::evaluation::hce::{init_evaluation_masks, MASKS};
 use crate::search::*;
 use crate::cache::TranspositionTable;
 use super::zobrist::init_zobrist;
+use super::cuckoo::init_cuckoo;
 
 // make A file be 0s
 const NOT_A_FILE: Bitboard = Bitboard(18374403900871474942);
@@ -27,6 +28,11 @@ pub static mut BISHOP_MASKS: [u64; 64] = [0; 64];
 // bishop attack masks
 pub static mut ROOK_MASKS: [u64; 64] = [0; 64];
 
+// squares strictly between two aligned squares [square][square]
+pub static mut BETWEEN: [[u64; 64]; 64] = [[0; 64]; 64];
+// full line through two aligned squares [square][square]
+pub static mut LINE: [[u64; 64]; 64] = [[0; 64]; 64];
+
 // bishop attack table [square][occupancies]
 pub static mut BISHOP_ATTACKS: [[u64; 512]; 64] = [[0; 512]; 64];
 // rook attack table [square][occupancies]
@@ -444,6 +450,30 @@ pub fn init_leapers_attacks() {
     }
 }
 
+// init between and line tables, needs the slider attack tables
+pub fn init_line_tables() {
+    for from in 0..64 {
+        for to in 0..64 {
+            if from == to {
+                continue;
+            }
+            let squares = (1u64 << from) | (1u64 << to);
+            unsafe {
+                // same diagonal
+                if get_bishop_attacks(from, Bitboard(0)) & (1u64 << to) != 0 {
+                    LINE[from][to] = (get_bishop_attacks(from, Bitboard(0)) & get_bishop_attacks(to, Bitboard(0))) | squares;
+                    BETWEEN[from][to] = get_bishop_attacks(from, Bitboard(1u64 << to)) & get_bishop_attacks(to, Bitboard(1u64 << from));
+                }
+                // same rank or file
+                if get_rook_attacks(from, Bitboard(0)) & (1u64 << to) != 0 {
+                    LINE[from][to] = (get_rook_attacks(from, Bitboard(0)) & get_rook_attacks(to, Bitboard(0))) | squares;
+                    BETWEEN[from][to] = get_rook_attacks(from, Bitboard(1u64 << to)) & get_rook_attacks(to, Bitboard(1u64 << from));
+                }
+            }
+        }
+    }
+}
+
 pub fn init_all() {
     // init leaper pieces attacks
     init_leapers_attacks();
@@ -452,9 +482,15 @@ pub fn init_all() {
     init_sliders_attacks(true); // bishop
     init_sliders_attacks(false); // rook
 
+    // init between and line tables
+    init_line_tables();
+
     // init random hash keys
     init_zobrist();
 
+    // init cuckoo tables for upcoming repetitions
+    init_cuckoo();
+
     unsafe {
         // init global transposition table
         TT = TranspositionTable::new(OPTIONS.hash_size as usize, true);
@@ -463,6 +499,13 @@ pub fn init_all() {
         MASKS = init_evaluation_masks();
     };
 
-    // init SEE (Static Exchange Evaluation)
-    init_see();
+    // init late move reductions
+    init_lmr_table();
+}
+
+// the unit tests run on several threads, the tables only get set up by the first one
+#[cfg(test)]
+pub fn init_tests() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(init_all);
 }
@@ -55,14 +55,20 @@ impl Bitboard {
     // get least significant 1st bit index
     pub fn ls1b(&self) -> isize {
         if self.0 != 0 {
-            return ((self.0 as i64 & (self.0 as i64).wrapping_neg()).wrapping_sub(1)).count_ones()
-                as isize;
+            self.0.trailing_zeros() as isize
         } else {
             // illegal index
             return -1;
         }
     }
 
+    // remove the least significant 1st bit and return its index, the bitboard must not be empty
+    pub fn pop_ls1b(&mut self) -> usize {
+        let square = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        square
+    }
+
     // check if bitboard is empty
     pub fn is_empty(&self) -> bool {
         return self.0 == 0;
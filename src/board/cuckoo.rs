@@ -0,0 +1,149 @@
+use crate::board::attacks::*;
+use crate::board::bitboard::*;
+use crate::board::position::*;
+use crate::board::zobrist::*;
+use crate::r#move::encode::*;
+
+// cuckoo tables of all the reversible moves, a move is found by the hash difference it makes
+// (see "Detecting upcoming repetitions" by Marcel van Kervinck)
+pub static mut CUCKOO_KEYS: [u64; 8192] = [0; 8192];
+pub static mut CUCKOO_MOVES: [u32; 8192] = [0; 8192];
+
+fn cuckoo_h1(key: u64) -> usize {
+    (key & 0x1fff) as usize
+}
+
+fn cuckoo_h2(key: u64) -> usize {
+    ((key >> 16) & 0x1fff) as usize
+}
+
+// init cuckoo tables, needs the zobrist keys and the attack tables
+pub fn init_cuckoo() {
+    unsafe {
+        CUCKOO_KEYS = [0; 8192];
+        CUCKOO_MOVES = [0; 8192];
+
+        // loop over every piece except pawns, their moves can't be undone
+        for piece in Piece::WhitePawn as usize..Piece::BlackKing as usize + 1 {
+            if piece % 6 == Piece::WhitePawn as usize {
+                continue;
+            }
+            for source in 0..64 {
+                let attacks = match piece % 6 {
+                    1 => KNIGHT_ATTACKS[source],
+                    2 => get_bishop_attacks(source, Bitboard(0)),
+                    3 => get_rook_attacks(source, Bitboard(0)),
+                    4 => get_queen_attacks(source, Bitboard(0)),
+                    _ => KING_ATTACKS[source],
+                };
+                // every move is stored once, with the lower square as the source
+                for target in source + 1..64 {
+                    if attacks & (1u64 << target) == 0 {
+                        continue;
+                    }
+                    let mut move_ = encode_move(source as u8, target as u8, piece as u8, 0, 0, 0, 0, 0);
+                    let mut key = ZOBRIST_KEYS[piece][source] ^ ZOBRIST_KEYS[piece][target] ^ ZOBRIST_TURN;
+
+                    // insert into the first slot, kicking out whatever is there into its other slot
+                    let mut index = cuckoo_h1(key);
+                    loop {
+                        std::mem::swap(&mut CUCKOO_KEYS[index], &mut key);
+                        std::mem::swap(&mut CUCKOO_MOVES[index], &mut move_);
+                        // empty slot
+                        if move_ == 0 {
+                            break;
+                        }
+                        index = if index == cuckoo_h1(key) { cuckoo_h2(key) } else { cuckoo_h1(key) };
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Position {
+    // check if the side to move has a move that reaches a position from earlier on, which means
+    // it can at least force a draw. positions before the root have to be repeated already
+    pub fn has_game_cycle(&self, ply: usize) -> bool {
+        // positions before the last irreversible move (or null move) can't come back
+        let end = (self.halfmove as usize).min(self.hash_stack.len());
+        if end < 3 {
+            return false;
+        }
+
+        let length = self.hash_stack.len();
+        // the hash `plies_ago` plies before the current position
+        let key = |plies_ago: usize| self.hash_stack[length - plies_ago];
+        let occupancy = self.occupancies[Side::WHITE].0 | self.occupancies[Side::BLACK].0;
+
+        for plies_ago in (3..=end).step_by(2) {
+            let move_key = self.hash ^ key(plies_ago);
+
+            let index = if unsafe { CUCKOO_KEYS[cuckoo_h1(move_key)] } == move_key {
+                cuckoo_h1(move_key)
+            } else if unsafe { CUCKOO_KEYS[cuckoo_h2(move_key)] } == move_key {
+                cuckoo_h2(move_key)
+            } else {
+                continue;
+            };
+
+            // the move has to be possible on the board as it is
+            let move_ = unsafe { CUCKOO_MOVES[index] };
+            let (from, to) = (source(move_) as usize, target(move_) as usize);
+            if unsafe { BETWEEN[from][to] } & occupancy != 0 {
+                continue;
+            }
+
+            // within the search a single repetition is enough
+            if ply > plies_ago {
+                return true;
+            }
+            // otherwise it has to be our piece making the move, not a move that led here
+            if ((1u64 << from) | (1u64 << to)) & self.occupancies[self.side].0 == 0 {
+                continue;
+            }
+            // and the position has to have occurred before already
+            if self.hash_stack[length - end..length - plies_ago].iter().rev().skip(1).step_by(2).any(|&hash| hash == key(plies_ago)) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    fn play(position: &mut Position, moves: &str) {
+        for move_string in moves.split_whitespace() {
+            let move_ = position.parse_uci(move_string);
+            assert_ne!(move_, 0, "illegal move {}", move_string);
+            position.make(move_);
+        }
+    }
+
+    #[test]
+    fn upcoming_repetition_is_found() {
+        init_tests();
+        let mut position = Position::from_fen(START_FEN).unwrap();
+        play(&mut position, "e2e4");
+
+        // root is after 1.e4, black can go back to the position after 1...e5 with Ng8
+        play(&mut position, "e7e5 g1f3 g8f6 f3g1");
+        assert!(!position.is_repetition(4));
+        assert!(position.has_game_cycle(4));
+
+        // going back to the root itself only counts if the root already occurred before it
+        let mut position = Position::from_fen(START_FEN).unwrap();
+        play(&mut position, "g1f3 g8f6 f3g1");
+        assert!(!position.has_game_cycle(3));
+
+        let mut position = Position::from_fen(START_FEN).unwrap();
+        play(&mut position, "g1f3 g8f6 f3g1 f6g8");
+        play(&mut position, "g1f3 g8f6 f3g1");
+        assert!(position.has_game_cycle(3));
+    }
+}
@@ -0,0 +1,414 @@
+use crate::board::position::*;
+use crate::evaluation::*;
+
+use std::fmt;
+
+// everything that can be wrong with a FEN string or the position it describes
+#[derive(Debug, Clone, PartialEq)]
+pub enum FenError {
+    // syntax
+    MissingField(&'static str),
+    TooManyFields(usize),
+    InvalidPiece(char),
+    InvalidRank(usize),
+    InvalidRankCount(usize),
+    InvalidSide(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidHalfmove(String),
+    InvalidFullmove(String),
+
+    // legality, see `Position::validate`
+    KingCount(usize, u32),
+    TooManyPieces(usize),
+    PawnOnBackRank(usize),
+    OpponentInCheck,
+    CastlingWithoutPieces(char),
+    ImpossibleEnPassant(usize),
+}
+
+const SIDE_NAMES: [&str; 2] = ["white", "black"];
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {}", field),
+            FenError::TooManyFields(count) => write!(f, "expected at most 6 fields, got {}", count),
+            FenError::InvalidPiece(piece) => write!(f, "invalid piece \"{}\"", piece),
+            FenError::InvalidRank(rank) => write!(f, "rank {} doesn't have 8 squares", 8 - rank),
+            FenError::InvalidRankCount(count) => write!(f, "expected 8 ranks, got {}", count),
+            FenError::InvalidSide(side) => write!(f, "invalid side to move \"{}\"", side),
+            FenError::InvalidCastling(castling) => write!(f, "invalid castling rights \"{}\"", castling),
+            FenError::InvalidEnPassant(square) => write!(f, "invalid en passant square \"{}\"", square),
+            FenError::InvalidHalfmove(halfmove) => write!(f, "invalid halfmove clock \"{}\"", halfmove),
+            FenError::InvalidFullmove(fullmove) => write!(f, "invalid fullmove number \"{}\"", fullmove),
+            FenError::KingCount(side, count) => write!(f, "{} has {} kings", SIDE_NAMES[*side], count),
+            FenError::TooManyPieces(side) => write!(f, "{} has too many pieces", SIDE_NAMES[*side]),
+            FenError::PawnOnBackRank(square) => write!(f, "pawn on {}", SQUARE_COORDS[*square]),
+            FenError::OpponentInCheck => write!(f, "the side not to move is in check"),
+            FenError::CastlingWithoutPieces(right) => write!(f, "castling right \"{}\" without the king and rook in place", right),
+            FenError::ImpossibleEnPassant(square) => write!(f, "no double pawn push can have happened for en passant on {}", SQUARE_COORDS[*square]),
+        }
+    }
+}
+
+impl Position {
+    // parse a full FEN string with all 6 fields
+    pub fn from_fen(fen: &str) -> Result<Position, FenError> {
+        Position::parse_fen(fen, false)
+    }
+
+    // parse a FEN string where the halfmove clock and fullmove number may be left out
+    pub fn from_fen_lenient(fen: &str) -> Result<Position, FenError> {
+        Position::parse_fen(fen, true)
+    }
+
+    fn parse_fen(fen: &str, lenient: bool) -> Result<Position, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() > 6 {
+            return Err(FenError::TooManyFields(fields.len()));
+        }
+        let field = |index: usize, name: &'static str| -> Result<&str, FenError> {
+            fields.get(index).copied().ok_or(FenError::MissingField(name))
+        };
+
+        let mut position = Position::empty();
+
+        // piece placement, from a8 to h1
+        let ranks: Vec<&str> = field(0, "piece placement")?.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::InvalidRankCount(ranks.len()));
+        }
+        for (rank, pieces) in ranks.iter().enumerate() {
+            let mut file = 0;
+            for char in pieces.chars() {
+                if let Some(empty) = char.to_digit(10) {
+                    if empty == 0 || empty > 8 {
+                        return Err(FenError::InvalidRank(rank));
+                    }
+                    file += empty as usize;
+                    continue;
+                }
+                let piece = "PNBRQKpnbrqk".find(char).ok_or(FenError::InvalidPiece(char))?;
+                if file >= 8 {
+                    return Err(FenError::InvalidRank(rank));
+                }
+                position.bitboards[piece].set(rank * 8 + file);
+                file += 1;
+            }
+            if file != 8 {
+                return Err(FenError::InvalidRank(rank));
+            }
+        }
+
+        // side to move
+        position.side = match field(1, "side to move")? {
+            "w" => Side::WHITE,
+            "b" => Side::BLACK,
+            side => return Err(FenError::InvalidSide(side.to_string())),
+        };
+
+        // castling rights
+        let castling = field(2, "castling rights")?;
+        if castling != "-" {
+            for char in castling.chars() {
+                // convert Shredder-FEN to FEN
+                let char = match char {
+                    // rook file given, check if it's lower than the king's file
+                    'A'..='H' => {
+                        let king_file = position.bitboards[Piece::WhiteKing as usize].ls1b() % 8;
+                        if (char as isize - 'A' as isize) < king_file { 'Q' } else { 'K' }
+                    }
+                    'a'..='h' => {
+                        let king_file = position.bitboards[Piece::BlackKing as usize].ls1b() % 8;
+                        if (char as isize - 'a' as isize) < king_file { 'q' } else { 'k' }
+                    }
+                    _ => char,
+                };
+
+                position.castle |= match char {
+                    'K' => Castling::WK as u8,
+                    'Q' => Castling::WQ as u8,
+                    'k' => Castling::BK as u8,
+                    'q' => Castling::BQ as u8,
+                    _ => return Err(FenError::InvalidCastling(castling.to_string())),
+                };
+            }
+        }
+
+        // en passant square
+        let enpassant = field(3, "en passant square")?;
+        if enpassant != "-" {
+            position.enpassant = *ASCII_TO_SQUARE.get(enpassant).ok_or(FenError::InvalidEnPassant(enpassant.to_string()))?;
+        }
+
+        // move counters, optional in lenient mode
+        let halfmove = if lenient { fields.get(4).copied().unwrap_or("0") } else { field(4, "halfmove clock")? };
+        position.halfmove = halfmove.parse::<u16>().map_err(|_| FenError::InvalidHalfmove(halfmove.to_string()))?;
+        let fullmove = if lenient { fields.get(5).copied().unwrap_or("1") } else { field(5, "fullmove number")? };
+        position.fullmove = fullmove.parse::<u16>().map_err(|_| FenError::InvalidFullmove(fullmove.to_string()))?;
+
+        // loop over white pieces bitboards
+        for piece in Piece::WhitePawn as usize..Piece::WhiteKing as usize + 1 {
+            position.occupancies[Side::WHITE].0 |= position.bitboards[piece].0;
+        }
+        // loop over black pieces bitboards
+        for piece in Piece::BlackPawn as usize..Piece::BlackKing as usize + 1 {
+            position.occupancies[Side::BLACK].0 |= position.bitboards[piece].0;
+        }
+
+        // initalize history vectors for unmake/make functions
+        position.halfmove_clocks_stack = Vec::with_capacity(32);
+        position.captured_pieces_stack = Vec::with_capacity(32);
+        position.castling_rights_stack = Vec::with_capacity(32);
+        position.en_passant_stack = Vec::with_capacity(32);
+        position.hash_stack = Vec::with_capacity(32);
+
+        position.hash = position.generate_hash_key();
+        position.null_moves = 0;
+
+        init_calculation(&mut position);
+
+        Ok(position)
+    }
+
+    // the first 4 fields of the FEN, shared with EPD
+    fn fen_fields(&self) -> String {
+        let mut fen = String::with_capacity(64);
+
+        // piece placement, from a8 to h1
+        for rank in 0..8 {
+            let mut empty = 0;
+            for file in 0..8 {
+                let square = rank * 8 + file;
+                match (0..12).find(|&piece| self.bitboards[piece].get(square) != 0) {
+                    Some(piece) => {
+                        if empty != 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push_str(ASCII_PIECES[piece]);
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty != 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank != 7 {
+                fen.push('/');
+            }
+        }
+
+        // side to move
+        fen.push_str(if self.side == Side::WHITE { " w " } else { " b " });
+
+        // castling rights
+        if self.castle == 0 {
+            fen.push('-');
+        }
+        for (right, char) in [(Castling::WK, 'K'), (Castling::WQ, 'Q'), (Castling::BK, 'k'), (Castling::BQ, 'q')] {
+            if self.castle & right as u8 != 0 {
+                fen.push(char);
+            }
+        }
+
+        // en passant square
+        fen.push(' ');
+        fen.push_str(if self.enpassant == Square::NoSquare { "-" } else { SQUARE_COORDS[self.enpassant as usize] });
+
+        fen
+    }
+
+    pub fn to_fen(&self) -> String {
+        format!("{} {} {}", self.fen_fields(), self.halfmove, self.fullmove)
+    }
+
+    // EPD of the position, the opcodes are written in order as "opcode operand;"
+    // e.g. [("bm", "e4"), ("id", "\"start\"")]
+    pub fn to_epd(&self, opcodes: &[(&str, &str)]) -> String {
+        let mut epd = self.fen_fields();
+        for (opcode, operand) in opcodes {
+            if operand.is_empty() {
+                epd.push_str(&format!(" {};", opcode));
+            } else {
+                epd.push_str(&format!(" {} {};", opcode, operand));
+            }
+        }
+        epd
+    }
+
+    // check that the position could come up in a game, the search relies on most of this
+    pub fn validate(&self) -> Result<(), FenError> {
+        for side in [Side::WHITE, Side::BLACK] {
+            // exactly one king each
+            let kings = self.bitboards[Piece::WhiteKing as usize + side * 6].count();
+            if kings != 1 {
+                return Err(FenError::KingCount(side, kings));
+            }
+            if self.occupancies[side].count() > 16 || self.bitboards[Piece::WhitePawn as usize + side * 6].count() > 8 {
+                return Err(FenError::TooManyPieces(side));
+            }
+        }
+
+        // pawns can't be on the first or last rank
+        let pawns = self.bitboards[Piece::WhitePawn as usize].0 | self.bitboards[Piece::BlackPawn as usize].0;
+        let back_ranks = 0xff000000000000ff;
+        if pawns & back_ranks != 0 {
+            return Err(FenError::PawnOnBackRank(pawns.trailing_zeros() as usize));
+        }
+
+        // the king of the side that just moved can't be left in check
+        let king_square = self.bitboards[Piece::WhiteKing as usize + (self.side ^ 1) * 6].ls1b() as usize;
+        if self.is_attacked(king_square, self.side) {
+            return Err(FenError::OpponentInCheck);
+        }
+
+        // castling rights need the king and the rook on their starting squares
+        let castling_pieces = [
+            (Castling::WK, 'K', Piece::WhiteKing, Square::E1, Piece::WhiteRook, Square::H1),
+            (Castling::WQ, 'Q', Piece::WhiteKing, Square::E1, Piece::WhiteRook, Square::A1),
+            (Castling::BK, 'k', Piece::BlackKing, Square::E8, Piece::BlackRook, Square::H8),
+            (Castling::BQ, 'q', Piece::BlackKing, Square::E8, Piece::BlackRook, Square::A8),
+        ];
+        for (right, char, king, king_square, rook, rook_square) in castling_pieces {
+            if self.castle & right as u8 != 0
+                && (self.bitboards[king as usize].get(king_square as usize) == 0 || self.bitboards[rook as usize].get(rook_square as usize) == 0) {
+                return Err(FenError::CastlingWithoutPieces(char));
+            }
+        }
+
+        // the en passant square has to be right behind a pawn that just made a double push
+        if self.enpassant != Square::NoSquare {
+            let square = self.enpassant as usize;
+            let occupancy = self.occupancies[Side::WHITE].0 | self.occupancies[Side::BLACK].0;
+            // the pushed pawn, and where it came from
+            let (rank, pawn_square, start_square, pawn) = if self.side == Side::WHITE {
+                (2, square + 8, square.wrapping_sub(8), Piece::BlackPawn)
+            } else {
+                (5, square.wrapping_sub(8), square + 8, Piece::WhitePawn)
+            };
+            if square / 8 != rank || self.bitboards[pawn as usize].get(pawn_square) == 0
+                || occupancy & ((1u64 << square) | (1u64 << start_square)) != 0 {
+                return Err(FenError::ImpossibleEnPassant(square));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::attacks::init_tests;
+    use crate::r#move::*;
+    use crate::uci::bench::BENCH_POSITIONS;
+
+    // the usual perft positions
+    #[rustfmt::skip]
+    const PERFT_POSITIONS: [&str; 6] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    ];
+
+    // parse the FEN written for the position and compare it with the position
+    fn assert_round_trip(position: &Position) {
+        let fen = position.to_fen();
+        let parsed = Position::from_fen(&fen).unwrap_or_else(|error| panic!("{}: {}", fen, error));
+        assert_eq!(parsed.to_fen(), fen);
+        assert_eq!(parsed.hash, position.hash, "{}", fen);
+        assert_eq!(parsed.validate(), Ok(()), "{}", fen);
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        init_tests();
+        for fen in PERFT_POSITIONS.iter().chain(BENCH_POSITIONS.iter()) {
+            let mut position = Position::from_fen(fen).unwrap();
+            assert_eq!(position.to_fen(), *fen);
+            assert_round_trip(&position);
+
+            // and every position two plies on, which covers en passant squares, castling and counters
+            let mut move_list = MoveList::new();
+            position.generate_moves(GenType::All, &mut move_list);
+            for &move_ in move_list.as_slice() {
+                position.make(move_.0);
+                assert_round_trip(&position);
+
+                let mut replies = MoveList::new();
+                position.generate_moves(GenType::All, &mut replies);
+                for &reply in replies.as_slice() {
+                    position.make(reply.0);
+                    assert_round_trip(&position);
+                    position.unmake(reply.0);
+                }
+                position.unmake(move_.0);
+            }
+        }
+    }
+
+    #[test]
+    fn epd_output() {
+        init_tests();
+        let position = Position::from_fen(PERFT_POSITIONS[0]).unwrap();
+        assert_eq!(position.to_epd(&[("bm", "e4"), ("id", "\"start\""), ("c0", "")]),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4; id \"start\"; c0;");
+    }
+
+    #[test]
+    fn fen_syntax_errors() {
+        init_tests();
+        let errors = [
+            ("", FenError::MissingField("piece placement")),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", FenError::MissingField("side to move")),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -", FenError::MissingField("halfmove clock")),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 x", FenError::TooManyFields(7)),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1", FenError::InvalidPiece('X')),
+            ("rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenError::InvalidRank(1)),
+            ("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenError::InvalidRank(2)),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPP/RNBQKBNR w KQkq - 0 1", FenError::InvalidRank(6)),
+            ("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenError::InvalidRankCount(7)),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1", FenError::InvalidSide("x".to_string())),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1", FenError::InvalidCastling("KQkx".to_string())),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1", FenError::InvalidEnPassant("e9".to_string())),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1", FenError::InvalidHalfmove("x".to_string())),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 -1", FenError::InvalidFullmove("-1".to_string())),
+        ];
+        for (fen, error) in errors {
+            assert_eq!(Position::from_fen(fen).err(), Some(error), "{}", fen);
+        }
+
+        // the counters may be left out in lenient mode, but they still have to be numbers
+        let position = Position::from_fen_lenient("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -").unwrap();
+        assert_eq!(position.to_fen(), PERFT_POSITIONS[0]);
+        assert_eq!(Position::from_fen_lenient("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x").err(),
+            Some(FenError::InvalidHalfmove("x".to_string())));
+    }
+
+    #[test]
+    fn illegal_positions() {
+        init_tests();
+        let errors = [
+            ("4k3/8/8/8/8/8/8/3KK3 w - - 0 1", FenError::KingCount(Side::WHITE, 2)),
+            ("3kk3/8/8/8/8/8/8/4K3 w - - 0 1", FenError::KingCount(Side::BLACK, 2)),
+            ("4k3/8/8/8/8/P7/PPPPPPPP/4K3 w - - 0 1", FenError::TooManyPieces(Side::WHITE)),
+            ("rnbqkbnr/pppppppp/n7/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenError::TooManyPieces(Side::BLACK)),
+            ("P3k3/8/8/8/8/8/8/4K3 w - - 0 1", FenError::PawnOnBackRank(Square::A8 as usize)),
+            ("4k3/8/8/8/8/8/8/4K2p w - - 0 1", FenError::PawnOnBackRank(Square::H1 as usize)),
+            ("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1", FenError::OpponentInCheck),
+            ("4k3/8/8/8/8/8/8/4K3 w K - 0 1", FenError::CastlingWithoutPieces('K')),
+            ("4k2r/8/8/8/8/8/8/4K3 w q - 0 1", FenError::CastlingWithoutPieces('q')),
+            ("4k3/8/8/8/8/8/8/4K3 w - e6 0 1", FenError::ImpossibleEnPassant(Square::E6 as usize)),
+            ("4k3/8/8/4p3/8/8/8/4K3 w - e5 0 1", FenError::ImpossibleEnPassant(Square::E5 as usize)),
+        ];
+        for (fen, error) in errors {
+            let position = Position::from_fen(fen).unwrap_or_else(|error| panic!("{}: {}", fen, error));
+            assert_eq!(position.validate(), Err(error), "{}", fen);
+        }
+    }
+}
@@ -11,4 +11,8 @@ pub mod position;
 pub use position::*;
 
 pub mod zobrist;
-pub use zobrist::*;
\ No newline at end of file
+pub use zobrist::*;
+
+pub mod fen;
+
+pub mod cuckoo;
\ No newline at end of file
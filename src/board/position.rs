@@ -146,7 +146,7 @@ impl Position {
             enpassant: Square::NoSquare,
             castle: 15, // <--- all castles allowed
             halfmove: 0,
-            fullmove: 0,
+            fullmove: 1,
             hash: 0,
             null_moves: 0,
             halfmove_clocks_stack: Vec::with_capacity(32),
@@ -312,8 +312,8 @@ impl Position {
             let index = (piece - 6) as usize;
             self.pst_scores[color as usize][0] += PSQT[index][(field^56) as usize];
             self.pst_scores[color as usize][1] += PSQT_EG[index][(field^56) as usize];
-            self.material_scores[color as usize][0] += PIECE_VALUE[index];
-            self.material_scores[color as usize][1] += PIECE_VALUE_EG[index];
+            self.material_scores[color as usize][0] += unsafe { EVAL_PARAMS.piece_value[index] };
+            self.material_scores[color as usize][1] += unsafe { EVAL_PARAMS.piece_value_eg[index] };
         } else {
             match piece as usize {
                 // mobility
@@ -329,8 +329,8 @@ impl Position {
                 _ => ()
             }
 
-            self.material_scores[color as usize][0] += PIECE_VALUE[piece as usize];
-            self.material_scores[color as usize][1] += PIECE_VALUE_EG[piece as usize];
+            self.material_scores[color as usize][0] += unsafe { EVAL_PARAMS.piece_value[piece as usize] };
+            self.material_scores[color as usize][1] += unsafe { EVAL_PARAMS.piece_value_eg[piece as usize] };
             self.pst_scores[color as usize][0] += PSQT[piece as usize][field as usize];
             self.pst_scores[color as usize][1] += PSQT_EG[piece as usize][field as usize];
         }
@@ -345,13 +345,13 @@ impl Position {
         // -6 the piece index if its black
         if color == 1 {
             let index = (piece - 6) as usize;
-            self.material_scores[color as usize][0] -= PIECE_VALUE[index];
-            self.material_scores[color as usize][1] -= PIECE_VALUE_EG[index];
+            self.material_scores[color as usize][0] -= unsafe { EVAL_PARAMS.piece_value[index] };
+            self.material_scores[color as usize][1] -= unsafe { EVAL_PARAMS.piece_value_eg[index] };
             self.pst_scores[color as usize][0] -= PSQT[index][(field^56) as usize];
             self.pst_scores[color as usize][1] -= PSQT_EG[index][(field^56) as usize];
         } else {          
-            self.material_scores[color as usize][0] -= PIECE_VALUE[piece as usize];
-            self.material_scores[color as usize][1] -= PIECE_VALUE_EG[piece as usize];
+            self.material_scores[color as usize][0] -= unsafe { EVAL_PARAMS.piece_value[piece as usize] };
+            self.material_scores[color as usize][1] -= unsafe { EVAL_PARAMS.piece_value_eg[piece as usize] };
             self.pst_scores[color as usize][0] -= PSQT[piece as usize][field as usize];
             self.pst_scores[color as usize][1] -= PSQT_EG[piece as usize][field as usize];
         }
@@ -609,7 +609,7 @@ impl Position {
         // }
 
         // update half move clock
-        if piece == 0 || capture != 0 {
+        if piece % 6 == Piece::WhitePawn as u8 || capture != 0 {
             self.halfmove = 0;
         } else {
             self.halfmove += 1;
@@ -724,12 +724,13 @@ impl Position {
         }
         // loop over board ranks
         for rank in 0..8 {
+            let mut line = String::new();
             for file in 0..8 {
                 // init square
                 let square = rank * 8 + file;
 
                 if file == 0 {
-                    print!("{}  ", 8 - rank);
+                    line.push_str(&format!("{}  ", 8 - rank));
                 }
 
                 // define piece
@@ -743,18 +744,18 @@ impl Position {
                 }
 
                 if piece == -1 {
-                    print!(". ");
+                    line.push_str(". ");
                 } else {
-                    print!("{} ", pieces[piece as usize]);
+                    line.push_str(&format!("{} ", pieces[piece as usize]));
                 }
             }
             // print new line every rank
-            println!();
+            send!("{}", line);
         }
         // print board files
-        println!("   a b c d e f g h\n");
+        send!("   a b c d e f g h\n");
         // print side to move
-        println!(
+        send!(
             "   Side to move: {}",
             if self.side == Side::WHITE {
                 "White"
@@ -763,7 +764,7 @@ impl Position {
             }
         );
         // print enpassant
-        println!(
+        send!(
             "   Enpassant: {}",
             if self.enpassant == Square::NoSquare {
                 "None"
@@ -772,7 +773,7 @@ impl Position {
             }
         );
         // print castling rights
-        println!(
+        send!(
             "   Castling: {}",
             if self.castle == 0 {
                 "None".to_string()
@@ -802,211 +803,80 @@ impl Position {
             }
         );
         // print hash key
-        println!("   Hash: {}", self.hash);
+        send!("   Hash: {}", self.hash);
         // print halfmove clock
-        println!("   Halfmove clock: {}", self.halfmove);
+        send!("   Halfmove clock: {}", self.halfmove);
         // print fullmove number
-        println!("   Fullmove number: {}\n", self.fullmove);
+        send!("   Fullmove number: {}\n", self.fullmove);
     }
 
-    pub fn from_fen(fen: &str) -> Position {
-        let mut position = Position::empty();
-
-        let mut square = 0;
-        let mut rank = 0;
-        let mut file = 0;
-        let mut index = 0;
-        for x in fen.split_whitespace() {
-            if index == 0 {
-                for i in x.chars() {
-                    match i {
-                        '1' => {
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        '2' => {
-                            file += 2;
-                            square = rank * 8 + file;
-                        }
-                        '3' => {
-                            file += 3;
-                            square = rank * 8 + file;
-                        }
-                        '4' => {
-                            file += 4;
-                            square = rank * 8 + file;
-                        }
-                        '5' => {
-                            file += 5;
-                            square = rank * 8 + file;
-                        }
-                        '6' => {
-                            file += 6;
-                            square = rank * 8 + file;
-                        }
-                        '7' => {
-                            file += 7;
-                            square = rank * 8 + file;
-                        }
-                        '8' => {
-                            file += 8;
-                            square = rank * 8 + file;
-                        }
-                        'P' => {
-                            position.bitboards[Piece::WhitePawn as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        'N' => {
-                            position.bitboards[Piece::WhiteKnight as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        'B' => {
-                            position.bitboards[Piece::WhiteBishop as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        'R' => {
-                            position.bitboards[Piece::WhiteRook as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        'Q' => {
-                            position.bitboards[Piece::WhiteQueen as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        'K' => {
-                            position.bitboards[Piece::WhiteKing as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        'p' => {
-                            position.bitboards[Piece::BlackPawn as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        'n' => {
-                            position.bitboards[Piece::BlackKnight as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        'b' => {
-                            position.bitboards[Piece::BlackBishop as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        'r' => {
-                            position.bitboards[Piece::BlackRook as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        'q' => {
-                            position.bitboards[Piece::BlackQueen as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        'k' => {
-                            position.bitboards[Piece::BlackKing as usize].set(square);
-                            file += 1;
-                            square = rank * 8 + file;
-                        }
-                        '/' => {
-                            rank += 1;
-                            file = 0;
-                        }
-                        _ => (),
-                    }
-                }
-            } else if index == 1 {
-                if x == "w" {
-                    position.side = Side::WHITE;
-                } else {
-                    position.side = Side::BLACK;
-                }
-            } else if index == 2 {
-                for i in x.chars() {
-                    // convert Shredder-FEN to FEN
-                    let char;
-                    let king_file = position.bitboards[Piece::WhiteKing as usize].ls1b() % 8;
-                    if i.is_uppercase() && (i != 'K' || i != 'Q') {
-                        // check if file is lower than king
-                        if i as isize - 65 < king_file {
-                            char = 'Q';
-                        } else {
-                            char = 'K';
-                        }
-                    } else if i != 'k' || i != 'q' {
-                        // check if file is lower than king
-                        if i as isize - 97 < king_file {
-                            char = 'q';
-                        } else {
-                            char = 'k';
-                        }
-                    } else {
-                        char = i;
-                    }
-                        
-                    match char {
-                        'K' => {
-                            position.castle |= Castling::WK as u8;
-                        }
-                        'Q' => {
-                            position.castle |= Castling::WQ as u8;
-                        }
-                        'k' => {
-                            position.castle |= Castling::BK as u8;
-                        }
-                        'q' => {
-                            position.castle |= Castling::BQ as u8;
-                        }
-                        _ => (),
-                    }
-                }
-            } else if index == 3 {
-                if x != "-" {
-                    position.enpassant = *ASCII_TO_SQUARE.get(x).unwrap_or_else(|| {
-                        return &Square::NoSquare;
-                    });
-                    if position.enpassant == Square::NoSquare {
-                        println!("info string Invalid fen given");
-                        return Position::empty();
-                    }
-                }
-            } else if index == 4 {
-                position.halfmove = x.parse::<u16>().unwrap();
-            } else if index == 5 {
-                position.fullmove = x.parse::<u16>().unwrap();
-            }
-            index += 1;
-        }
+    // board plus everything useful for debugging, for the "d" command
+    pub fn show_debug(&self) {
+        self.show(false);
+        send!("   Fen: {}", self.to_fen());
+        send!("   Key: {:016X}", self.hash);
 
-        // loop over white pieces bitboards
-        for piece in Piece::WhitePawn as usize..Piece::WhiteKing as usize + 1 {
-            position.occupancies[Side::WHITE].0 |= position.bitboards[piece].0;
+        let mut checkers = Bitboard(self.checkers());
+        let mut squares = Vec::new();
+        while checkers.0 != 0 {
+            squares.push(SQUARE_COORDS[checkers.pop_ls1b()]);
         }
-        // loop over black pieces bitboards
-        for piece in Piece::BlackPawn as usize..Piece::BlackKing as usize + 1 {
-            position.occupancies[Side::BLACK].0 |= position.bitboards[piece].0;
+        send!("   Checkers: {}", squares.join(" "));
+        send!("   Eval: {} (side to move)\n", evaluate(self));
+    }
+
+    // pieces of both sides attacking a square with the given occupancy
+    pub fn attackers_to(&self, square: usize, occupancy: u64) -> u64 {
+        let bishops_queens = self.bitboards[Piece::WhiteBishop as usize].0 | self.bitboards[Piece::BlackBishop as usize].0
+            | self.bitboards[Piece::WhiteQueen as usize].0 | self.bitboards[Piece::BlackQueen as usize].0;
+        let rooks_queens = self.bitboards[Piece::WhiteRook as usize].0 | self.bitboards[Piece::BlackRook as usize].0
+            | self.bitboards[Piece::WhiteQueen as usize].0 | self.bitboards[Piece::BlackQueen as usize].0;
+        unsafe {
+            (PAWN_ATTACKS[Side::BLACK][square] & self.bitboards[Piece::WhitePawn as usize].0)
+                | (PAWN_ATTACKS[Side::WHITE][square] & self.bitboards[Piece::BlackPawn as usize].0)
+                | (KNIGHT_ATTACKS[square] & (self.bitboards[Piece::WhiteKnight as usize].0 | self.bitboards[Piece::BlackKnight as usize].0))
+                | (KING_ATTACKS[square] & (self.bitboards[Piece::WhiteKing as usize].0 | self.bitboards[Piece::BlackKing as usize].0))
+                | (get_bishop_attacks(square, Bitboard(occupancy)) & bishops_queens)
+                | (get_rook_attacks(square, Bitboard(occupancy)) & rooks_queens)
         }
-        // init all occupancies
-        // position.occupancies[Side::BOTH].0 |= position.occupancies[Side::WHITE].0;
-        // position.occupancies[Side::BOTH].0 |= position.occupancies[Side::BLACK].0;
+    }
 
-        // initalize history vectors for unmake/make functions
-        position.halfmove_clocks_stack = Vec::with_capacity(32);
-        position.captured_pieces_stack = Vec::with_capacity(32);
-        position.castling_rights_stack = Vec::with_capacity(32);
-        position.en_passant_stack = Vec::with_capacity(32);
-        position.hash_stack = Vec::with_capacity(32);
+    // enemy pieces giving check to the side to move
+    pub fn checkers(&self) -> u64 {
+        let king_square = self.bitboards[Piece::WhiteKing as usize + self.side * 6].ls1b() as usize;
+        let both = self.occupancies[Side::WHITE].0 | self.occupancies[Side::BLACK].0;
+        self.attackers_to(king_square, both) & self.occupancies[self.side ^ 1].0
+    }
 
-        position.hash = position.generate_hash_key();
-        position.null_moves = 0;
+    // pieces of the given side pinned to their own king
+    pub fn pinned(&self, side: usize) -> u64 {
+        self.blockers(side, side)
+    }
 
-        init_calculation(&mut position);
+    // pieces of `owner` that are the only piece between the king of `side` and an enemy slider,
+    // for the king's own side these are pinned, for the other side they can give discovered check
+    pub fn blockers(&self, side: usize, owner: usize) -> u64 {
+        let king_square = self.bitboards[Piece::WhiteKing as usize + side * 6].ls1b() as usize;
+        let enemy = (side ^ 1) * 6;
+        let both = self.occupancies[Side::WHITE].0 | self.occupancies[Side::BLACK].0;
+
+        // enemy sliders that would attack the king on an empty board
+        let mut snipers = Bitboard(
+            (get_bishop_attacks(king_square, Bitboard(0)) & (self.bitboards[Piece::WhiteBishop as usize + enemy].0 | self.bitboards[Piece::WhiteQueen as usize + enemy].0))
+            | (get_rook_attacks(king_square, Bitboard(0)) & (self.bitboards[Piece::WhiteRook as usize + enemy].0 | self.bitboards[Piece::WhiteQueen as usize + enemy].0))
+        );
 
-        return position;
+        let mut blockers = 0;
+        while snipers.0 != 0 {
+            let sniper = snipers.ls1b() as usize;
+            // a single piece in between blocks the slider
+            let between = unsafe { BETWEEN[king_square][sniper] } & both;
+            if between.count_ones() == 1 {
+                blockers |= between & self.occupancies[owner].0;
+            }
+            snipers.pop(sniper);
+        }
+        blockers
     }
 
     pub fn is_attacked(&self, square: usize, side: usize) -> bool {
@@ -1084,114 +954,6 @@ impl Position {
         }
     }
 
-    pub fn get_attackers(&self, square: usize, side: usize) -> u8 {
-        let mut attackers = 0;
-        
-        let bishops_rooks = if side == Side::WHITE {
-            self.bitboards[Piece::BlackBishop as usize].0
-                | self.bitboards[Piece::BlackRook as usize].0
-        } else {
-            self.bitboards[Piece::WhiteBishop as usize].0
-                | self.bitboards[Piece::WhiteRook as usize].0
-        };
-        let rooks_queens = if side == Side::WHITE {
-            self.bitboards[Piece::BlackQueen as usize].0
-                | self.bitboards[Piece::BlackRook as usize].0
-        } else {
-            self.bitboards[Piece::WhiteQueen as usize].0
-                | self.bitboards[Piece::WhiteRook as usize].0
-        };
-        let bishops_queens = if side == Side::WHITE {
-            self.bitboards[Piece::BlackQueen as usize].0
-                | self.bitboards[Piece::BlackBishop as usize].0
-        } else {
-            self.bitboards[Piece::WhiteQueen as usize].0
-                | self.bitboards[Piece::WhiteBishop as usize].0
-        };
-
-        let occupancy_all = self.occupancies[Side::WHITE].0 | self.occupancies[Side::BLACK].0;
-
-        let mut knight_bishop_count = 0;
-        let king_attacks;
-        unsafe {
-            king_attacks = KING_ATTACKS[square];
-            if side == 0 {
-                if king_attacks & self.bitboards[Piece::BlackKing as usize].0 != 0 {
-                    attackers |= 1 << 7;
-                }
-                if get_queen_attacks(square, Bitboard(occupancy_all & !bishops_rooks)) & self.bitboards[Piece::BlackQueen as usize].0 != 0 {
-                    attackers |= 1 << 6;
-                }
-                let rook_attacks = get_rook_attacks(square, Bitboard(occupancy_all & !rooks_queens));
-                if rook_attacks & self.bitboards[Piece::BlackRook as usize].0 != 0 {
-                    if (rook_attacks & self.bitboards[Piece::BlackRook as usize].0).count_ones() == 1 {
-                        attackers |= 1 << 4;
-                    } else {
-                        attackers |= 3 << 4;
-                    }
-                }
-                let knight_attacks = KNIGHT_ATTACKS[square];
-                if knight_attacks & self.bitboards[Piece::BlackKnight as usize].0 != 0 {
-                    knight_bishop_count += (knight_attacks & self.bitboards[Piece::BlackKnight as usize].0).count_ones();
-                }
-                let bishop_attacks = get_bishop_attacks(square, Bitboard(occupancy_all & !bishops_queens));
-                if bishop_attacks & self.bitboards[Piece::BlackBishop as usize].0 != 0 {
-                    knight_bishop_count += (bishop_attacks & self.bitboards[Piece::BlackBishop as usize].0).count_ones();
-                }
-            } else {
-                if king_attacks & self.bitboards[Piece::WhiteKing as usize].0 != 0 {
-                    attackers |= 1 << 7;
-                }
-                if get_queen_attacks(square, Bitboard(occupancy_all & !bishops_rooks)) & self.bitboards[Piece::WhiteQueen as usize].0 != 0 {
-                    attackers |= 1 << 6;
-                }
-                let rook_attacks = get_rook_attacks(square, Bitboard(occupancy_all & !rooks_queens));
-                if rook_attacks & self.bitboards[Piece::WhiteRook as usize].0 != 0 {
-                    if (rook_attacks & self.bitboards[Piece::WhiteRook as usize].0).count_ones() == 1 {
-                        attackers |= 1 << 4;
-                    } else {
-                        attackers |= 3 << 4;
-                    }
-                }
-                let knight_attacks = KNIGHT_ATTACKS[square];
-                if knight_attacks & self.bitboards[Piece::WhiteKnight as usize].0 != 0 {
-                    knight_bishop_count += (knight_attacks & self.bitboards[Piece::WhiteKnight as usize].0).count_ones();
-                }
-                let bishop_attacks = get_bishop_attacks(square, Bitboard(occupancy_all & !bishops_queens));
-                if bishop_attacks & self.bitboards[Piece::WhiteBishop as usize].0 != 0 {
-                    knight_bishop_count += (bishop_attacks & self.bitboards[Piece::WhiteBishop as usize].0).count_ones();
-                }
-            }
-        }
-
-        if knight_bishop_count != 0 {
-            if knight_bishop_count == 1 {
-                attackers |= 1 << 1;
-            } else if knight_bishop_count == 2 {
-                attackers |= 3 << 1;
-            } else {
-                attackers |= 7 << 1;
-            }
-        }
-
-        let sq_bb = SHIFT_LOCATIONS[square];
-        let enemy_pawns = if side == Side::WHITE {
-            self.bitboards[Piece::BlackPawn as usize].0 & king_attacks
-        } else {
-            self.bitboards[Piece::WhitePawn as usize].0 & king_attacks
-        };
-        let attacking_pawns = if side == Side::WHITE {
-            (enemy_pawns >> 7 | enemy_pawns >> 9) & sq_bb
-        } else {
-            (enemy_pawns << 7 | enemy_pawns << 9) & sq_bb
-        };
-        if attacking_pawns != 0 {
-            attackers |= 1;
-        }
-
-        return attackers;
-    }
-
     pub fn show_attacked(&self, side: usize) {
         for rank in 0..8 {
             for file in 0..8 {
@@ -1228,6 +990,9 @@ impl Position {
             self.fullmove += 1;
         }
 
+        // nothing before a null move can be repeated
+        self.halfmove = 0;
+
         self.null_moves += 1;
         self.side = enemy_color;
         self.hash ^= unsafe { ZOBRIST_TURN };
@@ -1236,7 +1001,7 @@ impl Position {
     pub fn unmake_null_move(&mut self) {
         let color = self.side ^ 1;
 
-        self.halfmove_clocks_stack.pop().unwrap();
+        self.halfmove = self.halfmove_clocks_stack.pop().unwrap();
         self.hash = self.hash_stack.pop().unwrap();
         self.enpassant = self.en_passant_stack.pop().unwrap();
         self.castle = self.castling_rights_stack.pop().unwrap();
@@ -1257,23 +1022,20 @@ impl Position {
         return false;
     }
 
-    pub fn is_threefold(&self) -> bool {
-        if self.hash_stack.len() < 6 || self.null_moves > 0 {
-            return false;
-        }
+    // check if the position is a repetition, `ply` is the distance to the root of the search
+    // a repetition within the search is enough, positions from before the root need to occur three times
+    pub fn is_repetition(&self, ply: usize) -> bool {
+        // positions before the last irreversible move (or null move) can't repeat
+        let end = (self.halfmove as usize).min(self.hash_stack.len());
+        let length = self.hash_stack.len();
 
         let mut repetitions_count = 1;
-        let mut from = self.hash_stack.len().wrapping_sub(self.halfmove as usize);
-        let to = self.hash_stack.len() - 1;
-
-        if from > 1024 {
-            from = 0;
-        }
-
-        for hash_index in (from..to).rev().step_by(2) {
-            if self.hash_stack[hash_index] == self.hash {
+        for plies_ago in (4..=end).step_by(2) {
+            if self.hash_stack[length - plies_ago] == self.hash {
+                if plies_ago < ply {
+                    return true;
+                }
                 repetitions_count += 1;
-
                 if repetitions_count >= 3 {
                     return true;
                 }
@@ -1291,4 +1053,71 @@ impl Position {
         return self.halfmove >= 100;
     }
 
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    // play moves given in UCI notation, like "position ... moves" does
+    fn play(position: &mut Position, moves: &str) {
+        for move_string in moves.split_whitespace() {
+            let move_ = position.parse_uci(move_string);
+            assert_ne!(move_, 0, "illegal move {}", move_string);
+            position.make(move_);
+        }
+    }
+
+    #[test]
+    fn twofold_repetition_inside_the_search() {
+        init_tests();
+        let mut position = Position::from_fen(START_FEN).unwrap();
+        play(&mut position, "e2e4");
+
+        // root is after 1.e4, the position after 1...e5 comes back at ply 5
+        play(&mut position, "e7e5 g1f3 g8f6 f3g1");
+        assert!(!position.is_repetition(4));
+        play(&mut position, "f6g8");
+        assert!(position.is_repetition(5));
+    }
+
+    #[test]
+    fn threefold_repetition_across_the_root() {
+        init_tests();
+        // the root position occurs for the first time, coming back to it is only a twofold repetition
+        let mut position = Position::from_fen(START_FEN).unwrap();
+        play(&mut position, "g1f3 g8f6 f3g1 f6g8");
+        assert!(!position.is_repetition(4));
+
+        // the root position already occurred once in the game, so coming back to it is the third time
+        let mut position = Position::from_fen(START_FEN).unwrap();
+        play(&mut position, "g1f3 g8f6 f3g1 f6g8");
+        play(&mut position, "g1f3 g8f6 f3g1");
+        assert!(!position.is_repetition(3));
+        play(&mut position, "f6g8");
+        assert!(position.is_repetition(4));
+    }
+
+    #[test]
+    fn null_move_resets_the_repetition_window() {
+        init_tests();
+        let mut position = Position::from_fen(START_FEN).unwrap();
+        play(&mut position, "e2e4 e7e5");
+
+        // without null moves the position after 1.Nf3 comes back at ply 5
+        play(&mut position, "g1f3 b8c6 f3g1 c6b8 g1f3");
+        assert!(position.is_repetition(5));
+
+        // with black passing twice it's the same position after the same number of plies,
+        // but nothing before a null move counts
+        let mut position = Position::from_fen(START_FEN).unwrap();
+        play(&mut position, "e2e4 e7e5 g1f3");
+        position.make_null_move();
+        play(&mut position, "f3g1");
+        position.make_null_move();
+        play(&mut position, "g1f3");
+        assert_eq!(position.halfmove, 1);
+        assert!(!position.is_repetition(5));
+    }
+}
@@ -1,19 +1,19 @@
+use crate::r#move::encode::*;
 use crate::search::*;
 use std::mem;
 
+// the bounds are named after the window edge the score was stored as: LOWER_BOUND entries failed
+// low (stored alpha, the real score is at most this), UPPER_BOUND entries failed high (stored beta,
+// the real score is at least this)
 pub const EXACT: u8 = 0;
 pub const LOWER_BOUND: u8 = 1;
 pub const UPPER_BOUND: u8 = 2;
 
-pub struct BestMove {
-    pub value: u32,
-}
-
 #[derive(Clone, Default, Debug)]
 pub struct TTData {
     pub hash: u64,       // unique chess position identifier
     pub score: i16,      // score (alpha/beta/PV)
-    pub best_move: u32,  // the best move to play
+    pub best_move: u16,  // the best move to play, in the compact encoding
     pub depth: u8,       // current search depth
     pub age: u16,        // the age of the entry so we can determine when to delete it
     pub flag: u8,        // the type of node (fail-high, fail-low, exact, invalid)
@@ -34,7 +34,7 @@ impl TranspositionTable {
             age: 0,
         };
         if !quiet {
-            println!("info string allocating {} KB and {} items for TT", bucket_size*mem::size_of::<TTData>()/1024, bucket_size);
+            send!("info string allocating {} KB and {} items for TT", bucket_size*mem::size_of::<TTData>()/1024, bucket_size);
         }
 
         if size != 0 {
@@ -49,7 +49,17 @@ impl TranspositionTable {
         self.table.resize(self.size, Default::default());
     }
 
-    pub fn probe(&self, alpha: i16, beta: i16, best_move: &mut BestMove, depth: u8, ply: u8, hash: u64) -> i16 {
+    // permille of the table used by the current search, estimated from the first 1000 entries
+    pub fn hashfull(&self) -> usize {
+        let sample = self.size.min(1000);
+        if sample == 0 {
+            return 0;
+        }
+        let used = self.table[..sample].iter().filter(|entry| entry.hash != 0 && entry.age == self.age).count();
+        used * 1000 / sample
+    }
+
+    pub fn probe(&self, alpha: i16, beta: i16, depth: u8, ply: u8, hash: u64) -> i16 {
         let entry = &self.table[hash as usize % self.size];
         if entry.hash == hash {
             if entry.depth >= depth {
@@ -71,16 +81,34 @@ impl TranspositionTable {
                     return beta;
                 }
             }
-
-            // store best move
-            best_move.value = entry.best_move;
         }
         // if hash entry doesn't exist
         return NO_ENTRY;
     }
 
-    pub fn write(&mut self, hash: u64, mut score: i16, best_move: u32, depth: u8, ply: u8, flag: u8) {
+    // get the compact best move stored for a position, 0 if there is none
+    // it still has to be decoded with `Position::decode_move`
+    pub fn get_move(&self, hash: u64) -> u16 {
+        let entry = &self.table[hash as usize % self.size];
+        if entry.hash == hash {
+            return entry.best_move;
+        }
+        0
+    }
+
+    // get the entry stored for a position
+    pub fn get_entry(&self, hash: u64) -> Option<&TTData> {
+        let entry = &self.table[hash as usize % self.size];
+        if entry.hash == hash {
+            return Some(entry);
+        }
+        None
+    }
+
+    pub fn write(&mut self, hash: u64, mut score: i16, best_move: Move, depth: u8, ply: u8, flag: u8) {
         let entry = &self.table[hash as usize % self.size];
+        // keep the move we already had for this position if no move raised alpha
+        let best_move = if best_move.is_null() && entry.hash == hash { entry.best_move } else { best_move.to_compact() };
         let replace;
         if entry.hash == 0 {
             replace = true;
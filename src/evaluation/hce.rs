@@ -4,6 +4,7 @@ use crate::board::*;
 use crate::evaluation::*;
 use crate::movegen::*;
 use crate::search::*;
+use std::ptr::addr_of;
 
 pub static mut MASKS: Masks = Masks::new();
 
@@ -29,9 +30,15 @@ impl Masks {
 
 // evaluation function
 pub fn evaluate(position: &Position) -> i16 {
+    evaluate_phase(position, position.phase() <= 7, &mut NoTrace)
+}
 
+// evaluation with either the midgame or the endgame terms, every term goes through the tracer
+pub fn evaluate_phase<T: Tracer>(position: &Position, phase: bool, trace: &mut T) -> i16 {
+    let params = unsafe { &*addr_of!(EVAL_PARAMS) };
     let mut score = 0;
-    let phase = position.phase() <= 7;
+    let white_doubled = (position.bitboards[0].0 & position.bitboards[0].0 << 8).count_ones() as i16;
+    let black_doubled = (position.bitboards[Piece::BlackPawn as usize].0 & position.bitboards[Piece::BlackPawn as usize].0 << 8).count_ones() as i16;
     if phase {
         // check if insufficent material
         if position.is_insufficent_material() {
@@ -39,38 +46,41 @@ pub fn evaluate(position: &Position) -> i16 {
         }
 
         // add material score
-        score += position.material_scores[0][1] - position.material_scores[1][1]; 
+        add_term(&mut score, trace, Term::Material, Side::WHITE, position.material_scores[0][1]);
+        add_term(&mut score, trace, Term::Material, Side::BLACK, position.material_scores[1][1]);
         // add piece square table score
-        score += position.pst_scores[0][1] - position.pst_scores[1][1];
+        add_term(&mut score, trace, Term::Pst, Side::WHITE, position.pst_scores[0][1]);
+        add_term(&mut score, trace, Term::Pst, Side::BLACK, position.pst_scores[1][1]);
         // add double pawn score
-        score += (position.bitboards[0].0 & position.bitboards[0].0 << 8).count_ones() as i16 * DOUBLED_PAWN_ENDING - 
-        (position.bitboards[Piece::BlackPawn as usize].0 & position.bitboards[Piece::BlackPawn as usize].0 << 8).count_ones() as i16 * DOUBLED_PAWN_ENDING;
+        add_term(&mut score, trace, Term::DoubledPawns, Side::WHITE, white_doubled * params.doubled_pawn_ending);
+        add_term(&mut score, trace, Term::DoubledPawns, Side::BLACK, black_doubled * params.doubled_pawn_ending);
         // add mobility score
-        score += position.mobility[2] * BISHOP - position.mobility[8] * BISHOP;
-        score += position.mobility[3] * ROOK_EG - position.mobility[9] * ROOK_EG;
-        score += position.mobility[4] * QUEEN_EG - position.mobility[10] * QUEEN_EG;
-        // add score to get king closer to the other for mate
-        score += force_king_corner(&position);
+        add_term(&mut score, trace, Term::Mobility, Side::WHITE, position.mobility[2] * params.bishop_mobility + position.mobility[3] * params.rook_mobility_eg + position.mobility[4] * params.queen_mobility_eg);
+        add_term(&mut score, trace, Term::Mobility, Side::BLACK, position.mobility[8] * params.bishop_mobility + position.mobility[9] * params.rook_mobility_eg + position.mobility[10] * params.queen_mobility_eg);
+        // add score to get king closer to the other for mate, it helps the side to move
+        let king_corner = force_king_corner(&position);
+        add_term(&mut score, trace, Term::KingCorner, position.side, if position.side == Side::WHITE { king_corner } else { -king_corner });
     } else {
         // add material score
-        score += position.material_scores[0][0] - position.material_scores[1][0]; 
+        add_term(&mut score, trace, Term::Material, Side::WHITE, position.material_scores[0][0]);
+        add_term(&mut score, trace, Term::Material, Side::BLACK, position.material_scores[1][0]);
         // add piece square table score
-        score += position.pst_scores[0][0] - position.pst_scores[1][0];
+        add_term(&mut score, trace, Term::Pst, Side::WHITE, position.pst_scores[0][0]);
+        add_term(&mut score, trace, Term::Pst, Side::BLACK, position.pst_scores[1][0]);
         // add double pawn score
-        score += (position.bitboards[0].0 & position.bitboards[0].0 << 8).count_ones() as i16 * DOUBLED_PAWN_OPENING - 
-        (position.bitboards[Piece::BlackPawn as usize].0 & position.bitboards[Piece::BlackPawn as usize].0 << 8).count_ones() as i16 * DOUBLED_PAWN_OPENING;
-
-        score += position.mobility[2] * BISHOP - position.mobility[8] * BISHOP;
-        score += position.mobility[3] * ROOK - position.mobility[9] * ROOK;
-        score += position.mobility[4] * QUEEN - position.mobility[10] * QUEEN;
+        add_term(&mut score, trace, Term::DoubledPawns, Side::WHITE, white_doubled * params.doubled_pawn_opening);
+        add_term(&mut score, trace, Term::DoubledPawns, Side::BLACK, black_doubled * params.doubled_pawn_opening);
+        // add mobility score
+        add_term(&mut score, trace, Term::Mobility, Side::WHITE, position.mobility[2] * params.bishop_mobility + position.mobility[3] * params.rook_mobility + position.mobility[4] * params.queen_mobility);
+        add_term(&mut score, trace, Term::Mobility, Side::BLACK, position.mobility[8] * params.bishop_mobility + position.mobility[9] * params.rook_mobility + position.mobility[10] * params.queen_mobility);
     }
-    score += calculate_all(&position, phase);
+    calculate_all(&position, phase, &mut score, trace);
 
     // count bishop pair
     if position.bitboards[Piece::WhiteBishop as usize].count() >= 2 {
-        score += BISHOP_PAIR;
+        add_term(&mut score, trace, Term::BishopPair, Side::WHITE, params.bishop_pair);
     } if position.bitboards[Piece::BlackBishop as usize].count() >= 2 {
-        score -= BISHOP_PAIR;
+        add_term(&mut score, trace, Term::BishopPair, Side::BLACK, params.bishop_pair);
     }
 
     // return final evaluation based on side
@@ -222,8 +232,8 @@ pub fn init_calculation(position: &mut Position) {
             while bitboard.0 != 0 {
                 let square = bitboard.ls1b();
 
-                score += PIECE_VALUE[piece_index];
-                score_eg += PIECE_VALUE_EG[piece_index];
+                score += unsafe { EVAL_PARAMS.piece_value[piece_index] };
+                score_eg += unsafe { EVAL_PARAMS.piece_value_eg[piece_index] };
                 if color_index == 0 {
                     pst_score += PSQT[piece_index][square as usize];
                     pst_eg_score += PSQT_EG[piece_index][square as usize];
@@ -268,8 +278,7 @@ pub fn init_calculation(position: &mut Position) {
 }
 
 // calculates all the different evaluation scores for the given position
-pub fn calculate_all(position: &Position, phase: bool) -> i16 {
-    let mut score = 0;
+pub fn calculate_all<T: Tracer>(position: &Position, phase: bool, score: &mut i16, trace: &mut T) {
     if phase {
         for piece_index in 0..Piece::BlackPawn as usize {
             let mut bitboard = position.bitboards[piece_index];
@@ -280,18 +289,18 @@ pub fn calculate_all(position: &Position, phase: bool) -> i16 {
                     if piece_index == 0 {
                         // isolated pawns and passed pawns
                         if position.side == 0 && (position.bitboards[0].0 & MASKS.isolated_masks[square as usize]) == 0 {
-                            score += ISOLATED_PAWN_ENDING;
+                            add_term(score, trace, Term::IsolatedPawns, Side::WHITE, EVAL_PARAMS.isolated_pawn_ending);
                         } else {
                             if (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.isolated_masks[square as usize]) == 0 {
-                                score -= ISOLATED_PAWN_ENDING;
+                                add_term(score, trace, Term::IsolatedPawns, Side::BLACK, EVAL_PARAMS.isolated_pawn_ending);
                             }
                         }
     
                         if position.side == 0 && (position.bitboards[0].0 & MASKS.white_passed_masks[square as usize]) == 0 {
-                            score += PASSED_PAWN_ENDING;
+                            add_term(score, trace, Term::PassedPawns, Side::WHITE, EVAL_PARAMS.passed_pawn_ending);
                         } else {
                             if (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.white_passed_masks[square as usize]) == 0 {
-                                score -= PASSED_PAWN_ENDING;
+                                add_term(score, trace, Term::PassedPawns, Side::BLACK, EVAL_PARAMS.passed_pawn_ending);
                             }
                         }
                     }
@@ -299,7 +308,7 @@ pub fn calculate_all(position: &Position, phase: bool) -> i16 {
                 bitboard.pop(square as usize);
             }
         }
-        return score;
+        return;
     }
     for piece_index in 0..Piece::BlackPawn as usize {
         let mut bitboard = position.bitboards[piece_index];
@@ -311,18 +320,18 @@ pub fn calculate_all(position: &Position, phase: bool) -> i16 {
                     0 => {
                         // isolated pawns and passed pawns
                         if position.side == 0 && (position.bitboards[0].0 & MASKS.isolated_masks[square as usize]) == 0 {
-                            score += ISOLATED_PAWN_OPENING;
+                            add_term(score, trace, Term::IsolatedPawns, Side::WHITE, EVAL_PARAMS.isolated_pawn_opening);
                         } else {
                             if (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.isolated_masks[square as usize]) == 0 {
-                                score -= ISOLATED_PAWN_OPENING;
+                                add_term(score, trace, Term::IsolatedPawns, Side::BLACK, EVAL_PARAMS.isolated_pawn_opening);
                             }
                         }
 
                         if position.side == 0 && (position.bitboards[0].0 & MASKS.white_passed_masks[square as usize]) == 0 {
-                            score += PASSED_PAWN_OPENING;
+                            add_term(score, trace, Term::PassedPawns, Side::WHITE, EVAL_PARAMS.passed_pawn_opening);
                         } else {
                             if (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.white_passed_masks[square as usize]) == 0 {
-                                score -= PASSED_PAWN_OPENING;
+                                add_term(score, trace, Term::PassedPawns, Side::BLACK, EVAL_PARAMS.passed_pawn_opening);
                             }
                         }
                     },
@@ -330,16 +339,16 @@ pub fn calculate_all(position: &Position, phase: bool) -> i16 {
                         // open files
                         if position.side == 0 && (position.bitboards[0].0 & MASKS.file_masks[square as usize]) == 0 {
                             if (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.file_masks[square as usize]) == 0 {
-                                score += OPEN_FILE;
+                                add_term(score, trace, Term::OpenFiles, Side::WHITE, EVAL_PARAMS.open_file);
                             } else {
-                                score += SEMI_OPEN_FILE;
+                                add_term(score, trace, Term::OpenFiles, Side::WHITE, EVAL_PARAMS.semi_open_file);
                             }
                         } else {
                             if (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.file_masks[square as usize]) == 0 {
                                 if (position.bitboards[0].0 & MASKS.file_masks[square as usize]) == 0 {
-                                    score -= OPEN_FILE;
+                                    add_term(score, trace, Term::OpenFiles, Side::BLACK, EVAL_PARAMS.open_file);
                                 } else {
-                                    score -= SEMI_OPEN_FILE;
+                                    add_term(score, trace, Term::OpenFiles, Side::BLACK, EVAL_PARAMS.semi_open_file);
                                 }
                             }
                         }
@@ -350,60 +359,60 @@ pub fn calculate_all(position: &Position, phase: bool) -> i16 {
                             if (position.bitboards[0].0 & MASKS.file_masks[square as usize]) == 0 {
                                 if (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.file_masks[square as usize]) == 0 {
                                     // let mut a = (score, 0, 0);
-                                    score -= OPEN_FILE_PENALTY;
+                                    add_term(score, trace, Term::KingSafety, Side::WHITE, -EVAL_PARAMS.open_file_penalty);
                                     // a.1 = score;
                                     // a.2 = OPEN_FILE_PENALTY;
                                     // println!("{:?}", a);
                                 } else {
-                                    score -= SEMI_OPEN_FILE_PENALTY;
+                                    add_term(score, trace, Term::KingSafety, Side::WHITE, -EVAL_PARAMS.semi_open_file_penalty);
                                 }
                             } else if square as usize % 8 != 0 && (position.bitboards[0].0 & MASKS.file_masks[square as usize - 1]) == 0 {
                                 if (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.file_masks[square as usize - 1]) == 0 {
                                     if (position.bitboards[Piece::BlackRook as usize].0 & MASKS.file_masks[square as usize - 1]) != 0 {
-                                        score -= 50;
+                                        add_term(score, trace, Term::KingSafety, Side::WHITE, -50);
                                     }
-                                    score -= SIDE_OPEN;
+                                    add_term(score, trace, Term::KingSafety, Side::WHITE, -EVAL_PARAMS.side_open);
                                 } else {
-                                    score -= SIDE_SEMI_OPEN;
+                                    add_term(score, trace, Term::KingSafety, Side::WHITE, -EVAL_PARAMS.side_semi_open);
                                 }
                             } else if square as usize % 8 != 7 && (position.bitboards[0].0 & MASKS.file_masks[square as usize + 1]) == 0 {
                                 if (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.file_masks[square as usize + 1]) == 0 {
                                     if (position.bitboards[Piece::BlackRook as usize].0 & MASKS.file_masks[square as usize + 1]) != 0 {
-                                        score -= 50;
+                                        add_term(score, trace, Term::KingSafety, Side::WHITE, -50);
                                     }
-                                    score -= SIDE_OPEN;
+                                    add_term(score, trace, Term::KingSafety, Side::WHITE, -EVAL_PARAMS.side_open);
                                 } else {
-                                    score -= SIDE_SEMI_OPEN;
+                                    add_term(score, trace, Term::KingSafety, Side::WHITE, -EVAL_PARAMS.side_semi_open);
                                 }
                             }
-                            score += (KING_ATTACKS[square as usize] & position.occupancies[0].0).count_ones() as i16 * KING_SHIELD;
+                            add_term(score, trace, Term::KingSafety, Side::WHITE, (KING_ATTACKS[square as usize] & position.occupancies[0].0).count_ones() as i16 * EVAL_PARAMS.king_shield);
                         } else {
                             if (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.file_masks[square as usize]) == 0 {
                                 if (position.bitboards[0].0 & MASKS.file_masks[square as usize]) == 0 {
-                                    score += OPEN_FILE_PENALTY;
+                                    add_term(score, trace, Term::KingSafety, Side::BLACK, -EVAL_PARAMS.open_file_penalty);
                                 } else {
-                                    score += SEMI_OPEN_FILE_PENALTY;
+                                    add_term(score, trace, Term::KingSafety, Side::BLACK, -EVAL_PARAMS.semi_open_file_penalty);
                                 }
                             } else if square as usize % 8 != 0 && (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.file_masks[square as usize - 1]) == 0 {
                                 if (position.bitboards[0].0 & MASKS.file_masks[square as usize - 1]) == 0 {
                                     if (position.bitboards[Piece::WhiteRook as usize].0 & MASKS.file_masks[square as usize - 1]) != 0 {
-                                        score += 50;
+                                        add_term(score, trace, Term::KingSafety, Side::BLACK, -50);
                                     }
-                                    score += SIDE_OPEN;
+                                    add_term(score, trace, Term::KingSafety, Side::BLACK, -EVAL_PARAMS.side_open);
                                 } else {
-                                    score += SIDE_SEMI_OPEN;
+                                    add_term(score, trace, Term::KingSafety, Side::BLACK, -EVAL_PARAMS.side_semi_open);
                                 }
                             } else if square as usize % 8 != 7 && (position.bitboards[Piece::BlackPawn as usize].0 & MASKS.file_masks[square as usize + 1]) == 0 {
                                 if (position.bitboards[0].0 & MASKS.file_masks[square as usize + 1]) == 0 {
                                     if (position.bitboards[Piece::WhiteRook as usize].0 & MASKS.file_masks[square as usize + 1]) != 0 {
-                                        score += 50;
+                                        add_term(score, trace, Term::KingSafety, Side::BLACK, -50);
                                     }
-                                    score += SIDE_OPEN;
+                                    add_term(score, trace, Term::KingSafety, Side::BLACK, -EVAL_PARAMS.side_open);
                                 } else {
-                                    score += SIDE_SEMI_OPEN;
+                                    add_term(score, trace, Term::KingSafety, Side::BLACK, -EVAL_PARAMS.side_semi_open);
                                 }
                             }
-                            score -= (KING_ATTACKS[square as usize] & position.occupancies[0].0).count_ones() as i16 * KING_SHIELD;
+                            add_term(score, trace, Term::KingSafety, Side::BLACK, (KING_ATTACKS[square as usize] & position.occupancies[0].0).count_ones() as i16 * EVAL_PARAMS.king_shield);
                         }
                     }
                     _ => ()
@@ -412,5 +421,4 @@ pub fn calculate_all(position: &Position, phase: bool) -> i16 {
             bitboard.pop(square as usize);
         }
     }
-    return score;
-}
\ No newline at end of file
+}
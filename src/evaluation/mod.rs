@@ -2,4 +2,7 @@ pub mod hce;
 pub use hce::*;
 
 pub mod parameters;
-pub use parameters::*;
\ No newline at end of file
+pub use parameters::*;
+
+pub mod trace;
+pub use trace::*;
\ No newline at end of file
@@ -1,3 +1,5 @@
+use std::ptr::addr_of_mut;
+
 // evaluation parameters and constants
 pub const PIECE_VALUE: [i16; 6] = [95, 370, 390, 590, 1100, 0];
 pub const PIECE_VALUE_EG: [i16; 6] = [115, 310, 270, 680, 1300, 0];
@@ -29,6 +31,113 @@ pub const ROOK_EG: i16 = 4;
 pub const QUEEN: i16 = 1;
 pub const QUEEN_EG: i16 = 2;
 
+// the weights above, kept mutable so they can be changed from the config file at startup
+pub struct EvalParameters {
+    pub piece_value: [i16; 6],
+    pub piece_value_eg: [i16; 6],
+    pub bishop_pair: i16,
+    pub doubled_pawn_opening: i16,
+    pub doubled_pawn_ending: i16,
+    pub semi_open_file: i16,
+    pub open_file: i16,
+    pub semi_open_file_penalty: i16,
+    pub open_file_penalty: i16,
+    pub side_semi_open: i16,
+    pub side_open: i16,
+    pub king_shield: i16,
+    pub isolated_pawn_opening: i16,
+    pub isolated_pawn_ending: i16,
+    pub passed_pawn_opening: i16,
+    pub passed_pawn_ending: i16,
+    pub bishop_mobility: i16,
+    pub rook_mobility: i16,
+    pub rook_mobility_eg: i16,
+    pub queen_mobility: i16,
+    pub queen_mobility_eg: i16,
+}
+
+impl EvalParameters {
+    pub const fn default() -> EvalParameters {
+        EvalParameters {
+            piece_value: PIECE_VALUE,
+            piece_value_eg: PIECE_VALUE_EG,
+            bishop_pair: BISHOP_PAIR,
+            doubled_pawn_opening: DOUBLED_PAWN_OPENING,
+            doubled_pawn_ending: DOUBLED_PAWN_ENDING,
+            semi_open_file: SEMI_OPEN_FILE,
+            open_file: OPEN_FILE,
+            semi_open_file_penalty: SEMI_OPEN_FILE_PENALTY,
+            open_file_penalty: OPEN_FILE_PENALTY,
+            side_semi_open: SIDE_SEMI_OPEN,
+            side_open: SIDE_OPEN,
+            king_shield: KING_SHIELD,
+            isolated_pawn_opening: ISOLATED_PAWN_OPENING,
+            isolated_pawn_ending: ISOLATED_PAWN_ENDING,
+            passed_pawn_opening: PASSED_PAWN_OPENING,
+            passed_pawn_ending: PASSED_PAWN_ENDING,
+            bishop_mobility: BISHOP,
+            rook_mobility: ROOK,
+            rook_mobility_eg: ROOK_EG,
+            queen_mobility: QUEEN,
+            queen_mobility_eg: QUEEN_EG,
+        }
+    }
+}
+
+// the material values in `piece_value` are only used by the evaluation, SEE and move ordering keep `PIECE_VALUE`
+pub static mut EVAL_PARAMS: EvalParameters = EvalParameters::default();
+
+// a weight that can be set by name
+pub struct EvalWeight {
+    pub name: &'static str,
+    pub value: fn(&mut EvalParameters) -> &mut i16,
+}
+
+#[rustfmt::skip]
+pub const EVAL_WEIGHTS: [EvalWeight; 29] = [
+    EvalWeight { name: "pawn_value", value: |p| &mut p.piece_value[0] },
+    EvalWeight { name: "knight_value", value: |p| &mut p.piece_value[1] },
+    EvalWeight { name: "bishop_value", value: |p| &mut p.piece_value[2] },
+    EvalWeight { name: "rook_value", value: |p| &mut p.piece_value[3] },
+    EvalWeight { name: "queen_value", value: |p| &mut p.piece_value[4] },
+    EvalWeight { name: "pawn_value_eg", value: |p| &mut p.piece_value_eg[0] },
+    EvalWeight { name: "knight_value_eg", value: |p| &mut p.piece_value_eg[1] },
+    EvalWeight { name: "bishop_value_eg", value: |p| &mut p.piece_value_eg[2] },
+    EvalWeight { name: "rook_value_eg", value: |p| &mut p.piece_value_eg[3] },
+    EvalWeight { name: "queen_value_eg", value: |p| &mut p.piece_value_eg[4] },
+    EvalWeight { name: "bishop_pair", value: |p| &mut p.bishop_pair },
+    EvalWeight { name: "doubled_pawn_opening", value: |p| &mut p.doubled_pawn_opening },
+    EvalWeight { name: "doubled_pawn_ending", value: |p| &mut p.doubled_pawn_ending },
+    EvalWeight { name: "semi_open_file", value: |p| &mut p.semi_open_file },
+    EvalWeight { name: "open_file", value: |p| &mut p.open_file },
+    EvalWeight { name: "semi_open_file_penalty", value: |p| &mut p.semi_open_file_penalty },
+    EvalWeight { name: "open_file_penalty", value: |p| &mut p.open_file_penalty },
+    EvalWeight { name: "side_semi_open", value: |p| &mut p.side_semi_open },
+    EvalWeight { name: "side_open", value: |p| &mut p.side_open },
+    EvalWeight { name: "king_shield", value: |p| &mut p.king_shield },
+    EvalWeight { name: "isolated_pawn_opening", value: |p| &mut p.isolated_pawn_opening },
+    EvalWeight { name: "isolated_pawn_ending", value: |p| &mut p.isolated_pawn_ending },
+    EvalWeight { name: "passed_pawn_opening", value: |p| &mut p.passed_pawn_opening },
+    EvalWeight { name: "passed_pawn_ending", value: |p| &mut p.passed_pawn_ending },
+    EvalWeight { name: "bishop_mobility", value: |p| &mut p.bishop_mobility },
+    EvalWeight { name: "rook_mobility", value: |p| &mut p.rook_mobility },
+    EvalWeight { name: "rook_mobility_eg", value: |p| &mut p.rook_mobility_eg },
+    EvalWeight { name: "queen_mobility", value: |p| &mut p.queen_mobility },
+    EvalWeight { name: "queen_mobility_eg", value: |p| &mut p.queen_mobility_eg },
+];
+
+// set an evaluation weight by its (case insensitive) name, false if there is no such weight
+// positions set up before the change keep their old material scores
+pub fn set_eval_weight(name: &str, value: i16) -> bool {
+    match EVAL_WEIGHTS.iter().find(|weight| weight.name.eq_ignore_ascii_case(name)) {
+        Some(weight) => {
+            unsafe { *(weight.value)(&mut *addr_of_mut!(EVAL_PARAMS)) = value; }
+            true
+        }
+        None => false,
+    }
+}
+
 // get rank from list
 #[rustfmt::skip]
 pub const GET_RANK: [i16; 64] = [
@@ -0,0 +1,94 @@
+use crate::board::position::*;
+use crate::evaluation::*;
+
+// the terms the evaluation is made of
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Term {
+    Material,
+    Pst,
+    DoubledPawns,
+    IsolatedPawns,
+    PassedPawns,
+    Mobility,
+    OpenFiles,
+    KingSafety,
+    BishopPair,
+    KingCorner,
+}
+
+pub const TERM_COUNT: usize = 10;
+pub const TERM_NAMES: [&str; TERM_COUNT] = [
+    "Material", "PST", "Doubled pawns", "Isolated pawns", "Passed pawns",
+    "Mobility", "Open files", "King safety", "Bishop pair", "King corner",
+];
+
+// receives every term the evaluation adds up, `score` is from the point of view of `side`
+pub trait Tracer {
+    fn add(&mut self, term: Term, side: usize, score: i16);
+}
+
+// used by the normal evaluation, compiles away completely
+pub struct NoTrace;
+
+impl Tracer for NoTrace {
+    #[inline(always)]
+    fn add(&mut self, _term: Term, _side: usize, _score: i16) {}
+}
+
+// collects the terms of a single evaluation [term][side]
+pub struct EvalTrace {
+    pub scores: [[i16; 2]; TERM_COUNT],
+}
+
+impl EvalTrace {
+    pub fn new() -> EvalTrace {
+        EvalTrace { scores: [[0; 2]; TERM_COUNT] }
+    }
+}
+
+impl Tracer for EvalTrace {
+    fn add(&mut self, term: Term, side: usize, score: i16) {
+        self.scores[term as usize][side] += score;
+    }
+}
+
+// add a term to the white relative score and report it to the tracer
+#[inline(always)]
+pub fn add_term<T: Tracer>(score: &mut i16, trace: &mut T, term: Term, side: usize, value: i16) {
+    *score += if side == Side::WHITE { value } else { -value };
+    trace.add(term, side, value);
+}
+
+// print the evaluation term by term, for the "eval" command
+pub fn print_eval_trace(position: &Position) {
+    // the evaluation uses either the midgame or the endgame terms, trace both
+    let mut midgame = EvalTrace::new();
+    let midgame_score = evaluate_phase(position, false, &mut midgame);
+    let mut endgame = EvalTrace::new();
+    let endgame_score = evaluate_phase(position, true, &mut endgame);
+
+    send!("          Term |    White MG    EG |    Black MG    EG |    Total MG    EG");
+    send!("---------------+-------------------+-------------------+-------------------");
+    for term in 0..TERM_COUNT {
+        let (mg, eg) = (midgame.scores[term], endgame.scores[term]);
+        send!(
+            "{:>14} | {:>11} {:>5} | {:>11} {:>5} | {:>11} {:>5}",
+            TERM_NAMES[term],
+            mg[Side::WHITE], eg[Side::WHITE],
+            mg[Side::BLACK], eg[Side::BLACK],
+            mg[Side::WHITE] - mg[Side::BLACK], eg[Side::WHITE] - eg[Side::BLACK],
+        );
+    }
+    send!("---------------+-------------------+-------------------+-------------------");
+
+    // the scores are relative to the side to move, the table is white relative
+    let relative = |score: i16| if position.side == Side::WHITE { score } else { -score };
+    send!("{:>14} | {:>17} | {:>17} | {:>11} {:>5}\n", "Total", "", "", relative(midgame_score), relative(endgame_score));
+
+    let endgame_phase = position.phase() <= 7;
+    send!("Phase: {} ({} terms used)", position.phase(), if endgame_phase { "endgame" } else { "midgame" });
+    if endgame_phase && position.is_insufficent_material() {
+        send!("Insufficient material, the evaluation is a draw");
+    }
+    send!("Final evaluation: {} (white side), {} (side to move)", relative(evaluate(position)), evaluate(position));
+}
@@ -1,15 +1,17 @@
 #[macro_use]
 extern crate lazy_static;
 
+// first so `send!` can be used by all the other modules
+#[macro_use]
+mod uci;
+use uci::*;
+
 mod board;
 use board::*;
 
 mod r#move;
 use r#move::*;
 
-mod uci;
-use uci::*;
-
 mod search;
 use search::*;
 
@@ -19,6 +21,8 @@ use evaluation::*;
 mod cache;
 use cache::*;
 
+mod tools;
+
 use std::mem::MaybeUninit;
 
 // use std::thread;
@@ -37,20 +41,7 @@ use std::mem::MaybeUninit;
 fn main() {
     init_all();
 
-    // debug mode variable
-    let debug = false;
-    if debug {
-        let mut position = Position::from_fen("1nbqkbnr/Pppppppp/8/8/8/8/8/7K w k - 0 1");
-        println!("{}", position.occupancies[0].is_empty());
-        // generate legal antichess moves
-        // let mut moves = antichess::generate_moves(&mut position);
-        // moves.show();
-
-        // print moves
-        // move_list.show();
-
-    } else {
-        // start the main UCI loop to handle commands
-        main_loop();
-    }
+    // handle the command line, starts the UCI loop unless told otherwise
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    run_cli(&args);
 }
\ No newline at end of file
@@ -1,3 +1,9 @@
+use crate::board::position::*;
+use crate::r#move::movegen::PROMOTED_PIECES;
+
+use std::fmt;
+use std::str::FromStr;
+
 // encode move
 pub fn encode_move(
     source: u8,
@@ -58,3 +64,113 @@ pub fn enpassant(move_: u32) -> u8 {
 pub fn castling(move_: u32) -> u8 {
     return ((move_ >> 23) & 0x1) as u8;
 }
+
+// compact 16-bit move flags, stored in the upper 4 bits next to the source and target squares
+pub const COMPACT_QUIET: u16 = 0;
+pub const COMPACT_DOUBLE: u16 = 1;
+pub const COMPACT_CASTLING: u16 = 2;
+pub const COMPACT_CAPTURE: u16 = 4;
+pub const COMPACT_ENPASSANT: u16 = 5;
+// promotions to knight, bishop, rook and queen, plus COMPACT_CAPTURE when capturing
+pub const COMPACT_PROMOTION: u16 = 8;
+
+// a move with the full encoding of `encode_move`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct Move(pub u32);
+
+impl Move {
+    pub const NULL: Move = Move(0);
+
+    pub fn source(self) -> u8 {
+        source(self.0)
+    }
+
+    pub fn target(self) -> u8 {
+        target(self.0)
+    }
+
+    pub fn piece(self) -> u8 {
+        get_piece(self.0)
+    }
+
+    pub fn promoted(self) -> u8 {
+        promoted(self.0)
+    }
+
+    pub fn is_capture(self) -> bool {
+        capture(self.0) != 0
+    }
+
+    pub fn is_double(self) -> bool {
+        double(self.0) != 0
+    }
+
+    pub fn is_enpassant(self) -> bool {
+        enpassant(self.0) != 0
+    }
+
+    pub fn is_castling(self) -> bool {
+        castling(self.0) != 0
+    }
+
+    pub fn is_null(self) -> bool {
+        self.0 == 0
+    }
+
+    // 16-bit encoding used by the TT and training data, source | target << 6 | flag << 12
+    // the moving piece isn't stored, so `Position::decode_move` is needed to get the move back
+    pub fn to_compact(self) -> u16 {
+        if self.is_null() {
+            return 0;
+        }
+        let flag = if self.promoted() != 0 {
+            // knight, bishop, rook and queen are 1-4 after taking the side away
+            COMPACT_PROMOTION | (self.promoted() as u16 % 6 - 1) | if self.is_capture() { COMPACT_CAPTURE } else { 0 }
+        } else if self.is_enpassant() {
+            COMPACT_ENPASSANT
+        } else if self.is_capture() {
+            COMPACT_CAPTURE
+        } else if self.is_castling() {
+            COMPACT_CASTLING
+        } else if self.is_double() {
+            COMPACT_DOUBLE
+        } else {
+            COMPACT_QUIET
+        };
+        self.source() as u16 | (self.target() as u16) << 6 | flag << 12
+    }
+}
+
+// UCI notation, e.g. "e2e4" or "e7e8q"
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_null() {
+            return write!(f, "0000");
+        }
+        write!(f, "{}{}{}", SQUARE_COORDS[self.source() as usize], SQUARE_COORDS[self.target() as usize], PROMOTED_PIECES[self.promoted() as usize])
+    }
+}
+
+// parse UCI notation, only the squares and the promotion piece (as a white piece) are known
+// without a position, `Position::parse_uci` gives the full move
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(move_string: &str) -> Result<Move, String> {
+        let move_string = move_string.to_lowercase();
+        if move_string.len() < 4 || move_string.len() > 5 || !move_string.is_ascii() {
+            return Err(format!("Invalid move \"{}\"", move_string));
+        }
+        let source_square = *ASCII_TO_SQUARE.get(&move_string[0..2]).ok_or(format!("Invalid source square \"{}\"", &move_string[0..2]))?;
+        let target_square = *ASCII_TO_SQUARE.get(&move_string[2..4]).ok_or(format!("Invalid target square \"{}\"", &move_string[2..4]))?;
+        let promoted = match move_string.chars().nth(4) {
+            None => 0,
+            Some('n') => Piece::WhiteKnight as u8,
+            Some('b') => Piece::WhiteBishop as u8,
+            Some('r') => Piece::WhiteRook as u8,
+            Some('q') => Piece::WhiteQueen as u8,
+            Some(piece) => return Err(format!("Invalid promotion piece \"{}\"", piece)),
+        };
+        Ok(Move(encode_move(source_square as u8, target_square as u8, 0, promoted, 0, 0, 0, 0)))
+    }
+}
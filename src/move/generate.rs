@@ -0,0 +1,357 @@
+use crate::board::attacks::*;
+use crate::board::bitboard::*;
+use crate::board::position::*;
+use crate::r#move::encode::*;
+use crate::r#move::movegen::*;
+
+// rank masks, a8 is square 0
+const RANK_2: u64 = 0x00FF_0000_0000_0000;
+const RANK_7: u64 = 0x0000_0000_0000_FF00;
+
+// promotion pieces in the order they get generated, relative to the side's pieces
+const PROMOTIONS: [usize; 4] = [
+    Piece::WhiteQueen as usize,
+    Piece::WhiteKnight as usize,
+    Piece::WhiteBishop as usize,
+    Piece::WhiteRook as usize,
+];
+
+// categories of moves to generate
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GenType {
+    All,
+    // captures, en passant and queen promotions
+    Captures,
+    // everything else, including under-promotions and castling
+    Quiets,
+    // quiet moves giving direct or discovered check, without promotions and castling
+    QuietChecks,
+}
+
+impl GenType {
+    fn captures(self) -> bool {
+        self == GenType::All || self == GenType::Captures
+    }
+
+    fn quiets(self) -> bool {
+        self != GenType::Captures
+    }
+}
+
+// squares each piece type gives check from and pieces that give discovered check when they move
+struct CheckInfo {
+    squares: [u64; 6],
+    discovered: u64,
+    king_square: usize,
+}
+
+impl CheckInfo {
+    // target squares of a piece that give check
+    fn targets(&self, piece_type: usize, source_square: usize) -> u64 {
+        if self.discovered & (1u64 << source_square) != 0 {
+            // leaving the line to the king uncovers the check
+            return unsafe { !LINE[self.king_square][source_square] } | self.squares[piece_type];
+        }
+        self.squares[piece_type]
+    }
+}
+
+// what keeps the pieces other than the king from going anywhere
+struct Restrictions {
+    king_square: usize,
+    // squares that capture the checker or block the check, all of them when not in check
+    check_mask: u64,
+    pinned: u64,
+}
+
+impl Restrictions {
+    // squares a piece can move to without exposing its king, pinned pieces stay on the pin
+    fn pin_mask(&self, source_square: usize) -> u64 {
+        if self.pinned & (1u64 << source_square) != 0 {
+            return unsafe { LINE[self.king_square][source_square] };
+        }
+        !0
+    }
+}
+
+impl Position {
+    // generate legal moves of the given type
+    pub fn generate_moves(&self, gen_type: GenType, move_list: &mut MoveList) {
+        if self.side == Side::WHITE {
+            self.generate_legal::<{ Side::WHITE }>(gen_type, !0, move_list);
+        } else {
+            self.generate_legal::<{ Side::BLACK }>(gen_type, !0, move_list);
+        }
+    }
+
+    // generate the under-promotions that don't capture, they come with the quiets but aren't quiet moves
+    pub fn generate_under_promotions(&self, move_list: &mut MoveList) {
+        if self.side == Side::WHITE {
+            // the only quiet moves of a pawn about to promote are its under-promotions
+            let pawns = self.bitboards[Piece::WhitePawn as usize].0 & RANK_7;
+            if pawns != 0 {
+                self.generate_legal::<{ Side::WHITE }>(GenType::Quiets, pawns, move_list);
+            }
+        } else {
+            let pawns = self.bitboards[Piece::BlackPawn as usize].0 & RANK_2;
+            if pawns != 0 {
+                self.generate_legal::<{ Side::BLACK }>(GenType::Quiets, pawns, move_list);
+            }
+        }
+    }
+
+    // generate quiet moves that give check, used by the quiescence search
+    pub fn generate_quiet_checks(&self, move_list: &mut MoveList) {
+        self.generate_moves(GenType::QuietChecks, move_list);
+    }
+
+    // check if a move from somewhere else (TT, killers, PV) is legal in this position
+    pub fn is_legal_move(&self, move_: u32) -> bool {
+        if move_ == 0 {
+            return false;
+        }
+        // only the moves of the piece on the source square need to be generated
+        let mut move_list = MoveList::new();
+        let source_mask = 1u64 << source(move_);
+        if self.side == Side::WHITE {
+            self.generate_legal::<{ Side::WHITE }>(GenType::All, source_mask, &mut move_list);
+        } else {
+            self.generate_legal::<{ Side::BLACK }>(GenType::All, source_mask, &mut move_list);
+        }
+        move_list.as_slice().contains(&Move(move_))
+    }
+
+    // get the full move back from its 16-bit encoding, the null move if it isn't legal here
+    pub fn decode_move(&self, compact: u16) -> Move {
+        if compact == 0 {
+            return Move::NULL;
+        }
+        let mut move_list = MoveList::new();
+        let source_mask = 1u64 << (compact & 0x3f);
+        if self.side == Side::WHITE {
+            self.generate_legal::<{ Side::WHITE }>(GenType::All, source_mask, &mut move_list);
+        } else {
+            self.generate_legal::<{ Side::BLACK }>(GenType::All, source_mask, &mut move_list);
+        }
+        *move_list.as_slice().iter().find(|move_| move_.to_compact() == compact).unwrap_or(&Move::NULL)
+    }
+
+    // generate legal moves for the pieces on `source_mask`
+    fn generate_legal<const SIDE: usize>(&self, gen_type: GenType, source_mask: u64, move_list: &mut MoveList) {
+        let offset = SIDE * 6;
+        let us = self.occupancies[SIDE].0;
+        let them = self.occupancies[SIDE ^ 1].0;
+        let both = us | them;
+        let king_square = self.bitboards[Piece::WhiteKing as usize + offset].ls1b() as usize;
+        let checkers = self.checkers();
+        let pinned = self.pinned(SIDE);
+
+        // squares quiet moves and captures may go to
+        let quiet_targets = if gen_type.quiets() { !both } else { 0 };
+        let capture_targets = if gen_type.captures() { them } else { 0 };
+        let check_info = if gen_type == GenType::QuietChecks { Some(self.check_info::<SIDE>()) } else { None };
+
+        unsafe {
+            // king moves
+            if source_mask & (1u64 << king_square) != 0 {
+                let mut attacks = Bitboard(KING_ATTACKS[king_square] & (quiet_targets | capture_targets));
+                if let Some(check_info) = &check_info {
+                    attacks.0 &= check_info.targets(Piece::WhiteKing as usize, king_square);
+                }
+                // the king can't hide from a slider by standing in its way
+                let occupancy = both ^ (1u64 << king_square);
+                while attacks.0 != 0 {
+                    let target_square = attacks.pop_ls1b();
+                    if self.attackers_to(target_square, occupancy) & them == 0 {
+                        move_list.add(Move(encode_move(
+                            king_square as u8,
+                            target_square as u8,
+                            (Piece::WhiteKing as usize + offset) as u8,
+                            0,
+                            ((them >> target_square) & 1) as u8,
+                            0,
+                            0,
+                            0,
+                        )));
+                    }
+                }
+
+                // castling out of check is not allowed
+                if (gen_type == GenType::All || gen_type == GenType::Quiets) && checkers == 0 {
+                    self.generate_castling::<SIDE>(both, move_list);
+                }
+            }
+
+            // in double check only the king can move
+            if checkers.count_ones() > 1 {
+                return;
+            }
+            // in check the other pieces have to capture the checker or block the check
+            let check_mask = if checkers == 0 {
+                !0
+            } else {
+                BETWEEN[king_square][checkers.trailing_zeros() as usize] | checkers
+            };
+            let restrictions = Restrictions { king_square, check_mask, pinned };
+
+            self.generate_pawn_moves::<SIDE>(gen_type, source_mask, &restrictions, &check_info, move_list);
+
+            // knights, bishops, rooks and queens
+            let targets = (quiet_targets | capture_targets) & check_mask;
+            for piece in Piece::WhiteKnight as usize..Piece::WhiteKing as usize {
+                let mut bitboard = Bitboard(self.bitboards[piece + offset].0 & source_mask);
+                while bitboard.0 != 0 {
+                    let source_square = bitboard.pop_ls1b();
+                    let mut attacks = targets & match piece {
+                        1 => KNIGHT_ATTACKS[source_square],
+                        2 => get_bishop_attacks(source_square, Bitboard(both)),
+                        3 => get_rook_attacks(source_square, Bitboard(both)),
+                        _ => get_queen_attacks(source_square, Bitboard(both)),
+                    };
+                    // pinned pieces can only move along the pin
+                    attacks &= restrictions.pin_mask(source_square);
+                    if let Some(check_info) = &check_info {
+                        attacks &= check_info.targets(piece, source_square);
+                    }
+
+                    let mut attacks = Bitboard(attacks);
+                    while attacks.0 != 0 {
+                        let target_square = attacks.pop_ls1b();
+                        move_list.add(Move(encode_move(
+                            source_square as u8,
+                            target_square as u8,
+                            (piece + offset) as u8,
+                            0,
+                            ((them >> target_square) & 1) as u8,
+                            0,
+                            0,
+                            0,
+                        )));
+                    }
+                }
+            }
+        }
+    }
+
+    fn generate_pawn_moves<const SIDE: usize>(&self, gen_type: GenType, source_mask: u64, restrictions: &Restrictions, check_info: &Option<CheckInfo>, move_list: &mut MoveList) {
+        let offset = SIDE * 6;
+        let (king_square, check_mask) = (restrictions.king_square, restrictions.check_mask);
+        let piece = (Piece::WhitePawn as usize + offset) as u8;
+        let them = self.occupancies[SIDE ^ 1].0;
+        let both = self.occupancies[SIDE].0 | them;
+
+        // white pawns move towards the 8th rank, which is square 0
+        let push: isize = if SIDE == Side::WHITE { -8 } else { 8 };
+        let start_rank = if SIDE == Side::WHITE { RANK_2 } else { RANK_7 };
+        let promotion_rank = if SIDE == Side::WHITE { RANK_7 } else { RANK_2 };
+
+        unsafe {
+            let mut bitboard = Bitboard(self.bitboards[piece as usize].0 & source_mask);
+            while bitboard.0 != 0 {
+                let source_square = bitboard.pop_ls1b();
+                // pinned pawns can only move along the pin
+                let pin_mask = restrictions.pin_mask(source_square);
+                let promotion = (1u64 << source_square) & promotion_rank != 0;
+                // pushes have to give check too in QuietChecks mode
+                let push_mask = match check_info {
+                    Some(check_info) => check_mask & pin_mask & check_info.targets(Piece::WhitePawn as usize, source_square),
+                    None => check_mask & pin_mask,
+                };
+
+                // pushes
+                let target_square = (source_square as isize + push) as usize;
+                if both & (1u64 << target_square) == 0 {
+                    if promotion {
+                        if (1u64 << target_square) & check_mask & pin_mask != 0 {
+                            for promoted in PROMOTIONS {
+                                // queen promotions are searched with the captures, under-promotions with the quiets
+                                let wanted = if promoted == Piece::WhiteQueen as usize { gen_type.captures() } else { gen_type == GenType::All || gen_type == GenType::Quiets };
+                                if wanted {
+                                    move_list.add(Move(encode_move(source_square as u8, target_square as u8, piece, (promoted + offset) as u8, 0, 0, 0, 0)));
+                                }
+                            }
+                        }
+                    } else if gen_type.quiets() {
+                        if (1u64 << target_square) & push_mask != 0 {
+                            move_list.add(Move(encode_move(source_square as u8, target_square as u8, piece, 0, 0, 0, 0, 0)));
+                        }
+                        // double push
+                        if (1u64 << source_square) & start_rank != 0 {
+                            let double_square = (target_square as isize + push) as usize;
+                            if both & (1u64 << double_square) == 0 && (1u64 << double_square) & push_mask != 0 {
+                                move_list.add(Move(encode_move(source_square as u8, double_square as u8, piece, 0, 0, 1, 0, 0)));
+                            }
+                        }
+                    }
+                }
+
+                if gen_type.captures() {
+                    // captures
+                    let mut attacks = Bitboard(PAWN_ATTACKS[SIDE][source_square] & them & check_mask & pin_mask);
+                    while attacks.0 != 0 {
+                        let target_square = attacks.pop_ls1b();
+                        if promotion {
+                            for promoted in PROMOTIONS {
+                                move_list.add(Move(encode_move(source_square as u8, target_square as u8, piece, (promoted + offset) as u8, 1, 0, 0, 0)));
+                            }
+                        } else {
+                            move_list.add(Move(encode_move(source_square as u8, target_square as u8, piece, 0, 1, 0, 0, 0)));
+                        }
+                    }
+
+                    // enpassant
+                    if self.enpassant != Square::NoSquare && PAWN_ATTACKS[SIDE][source_square] & (1u64 << self.enpassant as usize) != 0 {
+                        let target_square = self.enpassant as usize;
+                        let captured_square = (target_square as isize - push) as usize;
+                        // the captured pawn has to be the checker or the pawn has to block the check
+                        if check_mask & ((1u64 << target_square) | (1u64 << captured_square)) != 0 {
+                            // both pawns leave their squares at once, so look for a discovered check
+                            let occupancy = (both ^ (1u64 << source_square) ^ (1u64 << captured_square)) | (1u64 << target_square);
+                            if self.attackers_to(king_square, occupancy) & them & !(1u64 << captured_square) == 0 {
+                                move_list.add(Move(encode_move(source_square as u8, target_square as u8, piece, 0, 1, 0, 1, 0)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // squares and pieces that give check to the enemy king
+    fn check_info<const SIDE: usize>(&self) -> CheckInfo {
+        let king_square = self.bitboards[Piece::WhiteKing as usize + (SIDE ^ 1) * 6].ls1b() as usize;
+        let both = Bitboard(self.occupancies[Side::WHITE].0 | self.occupancies[Side::BLACK].0);
+        unsafe {
+            let bishop = get_bishop_attacks(king_square, both);
+            let rook = get_rook_attacks(king_square, both);
+            CheckInfo {
+                // a pawn checks the king from where an enemy pawn on the king's square would attack
+                squares: [PAWN_ATTACKS[SIDE ^ 1][king_square], KNIGHT_ATTACKS[king_square], bishop, rook, bishop | rook, 0],
+                discovered: self.blockers(SIDE ^ 1, SIDE),
+                king_square,
+            }
+        }
+    }
+
+    // castling, the king must not be in check
+    fn generate_castling<const SIDE: usize>(&self, both: u64, move_list: &mut MoveList) {
+        let piece = (Piece::WhiteKing as usize + SIDE * 6) as u8;
+        let (king_side, queen_side) = if SIDE == Side::WHITE { (Castling::WK as u8, Castling::WQ as u8) } else { (Castling::BK as u8, Castling::BQ as u8) };
+        // squares of the back rank relative to the side
+        let rank = if SIDE == Side::WHITE { Square::A1 as usize } else { Square::A8 as usize };
+        let (b, c, d, e, f, g) = (rank + 1, rank + 2, rank + 3, rank + 4, rank + 5, rank + 6);
+
+        // king side, the squares between king and rook are empty and not attacked
+        if self.castle & king_side != 0 && both & ((1u64 << f) | (1u64 << g)) == 0
+            && !self.is_attacked(f, SIDE ^ 1) && !self.is_attacked(g, SIDE ^ 1)
+        {
+            move_list.add(Move(encode_move(e as u8, g as u8, piece, 0, 0, 0, 0, 1)));
+        }
+        // queen side, the b file square only has to be empty
+        if self.castle & queen_side != 0 && both & ((1u64 << b) | (1u64 << c) | (1u64 << d)) == 0
+            && !self.is_attacked(d, SIDE ^ 1) && !self.is_attacked(c, SIDE ^ 1)
+        {
+            move_list.add(Move(encode_move(e as u8, c as u8, piece, 0, 0, 0, 0, 1)));
+        }
+    }
+}
@@ -3,3 +3,6 @@ pub use movegen::*;
 
 pub mod encode;
 pub use encode::*;
+
+pub mod generate;
+pub use generate::*;
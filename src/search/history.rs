@@ -0,0 +1,100 @@
+use crate::board::position::*;
+use crate::r#move::encode::*;
+use crate::search::*;
+
+// history scores are kept within +-MAX_HISTORY by the gravity formula
+pub const MAX_HISTORY: i32 = 16384;
+// [previous piece][previous target][piece][target]
+pub const CONTINUATION_SIZE: usize = 12 * 64 * 12 * 64;
+
+// bonus for the move that caused a cutoff, and malus for the moves tried before it
+pub fn history_bonus(depth: u8) -> i32 {
+    let depth = depth as i32;
+    (16 * depth * depth + 32 * depth).min(1200)
+}
+
+// move an entry towards +-MAX_HISTORY, the closer it already is the smaller the change
+fn apply_gravity(entry: &mut i16, bonus: i32) {
+    *entry += (bonus - *entry as i32 * bonus.abs() / MAX_HISTORY) as i16;
+}
+
+fn continuation_index(previous: u32, move_: u32) -> usize {
+    ((get_piece(previous) as usize * 64 + target(previous) as usize) * 12 + get_piece(move_) as usize) * 64 + target(move_) as usize
+}
+
+impl Searcher {
+    // the move played `plies_ago` plies before the current node, 0 for none or a null move
+    pub fn previous_move(&self, plies_ago: usize) -> u32 {
+        if (self.ply as usize) < plies_ago {
+            return 0;
+        }
+        self.move_stack[self.ply as usize - plies_ago]
+    }
+
+    // butterfly history plus continuation history of the last two plies
+    pub fn quiet_history(&self, side: usize, move_: u32) -> i32 {
+        let mut score = self.history[side][source(move_) as usize][target(move_) as usize] as i32;
+        for plies_ago in 1..=2 {
+            let previous = self.previous_move(plies_ago);
+            if previous != 0 && !self.continuation_history.is_empty() {
+                score += self.continuation_history[continuation_index(previous, move_)] as i32;
+            }
+        }
+        score
+    }
+
+    pub fn capture_history(&self, position: &Position, move_: u32) -> i32 {
+        let captured = position.get_square_piece(target(move_) as usize) % 6;
+        self.capture_history[get_piece(move_) as usize][target(move_) as usize][captured] as i32
+    }
+
+    // the reply that refuted the previous move last time
+    pub fn counter_move(&self) -> u32 {
+        let previous = self.previous_move(1);
+        if previous == 0 {
+            return 0;
+        }
+        self.counter_moves[get_piece(previous) as usize][target(previous) as usize]
+    }
+
+    // reward the quiet move that caused a cutoff and punish the quiets searched before it
+    pub fn update_quiet_history(&mut self, side: usize, best_move: u32, tried: &[u32], depth: u8) {
+        let bonus = history_bonus(depth);
+        self.update_quiet(side, best_move, bonus);
+        for &move_ in tried {
+            self.update_quiet(side, move_, -bonus);
+        }
+
+        // remember the refutation of the previous move
+        let previous = self.previous_move(1);
+        if previous != 0 {
+            self.counter_moves[get_piece(previous) as usize][target(previous) as usize] = best_move;
+        }
+    }
+
+    fn update_quiet(&mut self, side: usize, move_: u32, bonus: i32) {
+        apply_gravity(&mut self.history[side][source(move_) as usize][target(move_) as usize], bonus);
+        for plies_ago in 1..=2 {
+            let previous = self.previous_move(plies_ago);
+            if previous != 0 && !self.continuation_history.is_empty() {
+                apply_gravity(&mut self.continuation_history[continuation_index(previous, move_)], bonus);
+            }
+        }
+    }
+
+    // reward the capture that caused a cutoff (if it was one) and punish the captures searched before it
+    pub fn update_capture_history(&mut self, position: &Position, best_move: Option<u32>, tried: &[u32], depth: u8) {
+        let bonus = history_bonus(depth);
+        if let Some(move_) = best_move {
+            self.update_capture(position, move_, bonus);
+        }
+        for &move_ in tried {
+            self.update_capture(position, move_, -bonus);
+        }
+    }
+
+    fn update_capture(&mut self, position: &Position, move_: u32, bonus: i32) {
+        let captured = position.get_square_piece(target(move_) as usize) % 6;
+        apply_gravity(&mut self.capture_history[get_piece(move_) as usize][target(move_) as usize][captured], bonus);
+    }
+}
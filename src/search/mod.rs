@@ -1,8 +1,20 @@
 pub mod search;
 pub use search::*;
 
+pub mod history;
+pub use history::*;
+
 pub mod ordering;
 pub use ordering::*;
 
+pub mod parameters;
+pub use parameters::*;
+
+pub mod picker;
+
+pub mod skill;
+pub use skill::*;
+
 pub mod see;
-pub use see::*;
\ No newline at end of file
+
+pub mod time;
\ No newline at end of file
@@ -27,8 +27,16 @@ pub const MVV_LVA: [[u16;6];6] = [
 	[100, 200, 300, 400, 500, 600,],
 ];
 
+// base scores, promotions come before captures and captures before quiet moves
+// the quiet base keeps maluses from the history tables above zero
+const PROMOTION_SCORE: u32 = 1 << 29;
+const CAPTURE_SCORE: u32 = 1 << 28;
+const QUIET_SCORE: u32 = 1 << 20;
+// one MVV LVA step is worth more than the whole capture history range, so the history only orders equal captures
+const MVV_LVA_SCALE: i32 = 2 * MAX_HISTORY + 1;
+
 impl Searcher {
-    pub fn sort_next_move(&self, moves: &mut [u32; 256], move_scores: &mut [u32; 256], start_index: usize, moves_count: usize) -> u32 {
+    pub fn sort_next_move(&self, moves: &mut [Move; 256], move_scores: &mut [u32; 256], start_index: usize, moves_count: usize) -> Move {
         let mut best_score = move_scores[start_index];
         let mut best_index = start_index;
     
@@ -54,86 +62,44 @@ impl Searcher {
         // score all the moves within a move list
         for count in 0..move_list.count as usize {
             // score move
-            move_scores[count] = (self.score_move(&position, move_list.moves[count]), move_list.moves[count]);
+            move_scores[count] = (self.score_move(&position, move_list.moves[count].0), move_list.moves[count].0);
         }
         move_scores.sort_by_key(|w| Reverse(w.0));
         return move_scores;
     }
 
-    // pub fn score_move_q(&mut self, position: &Position, move_: u32) -> u32 {
-    //     if enpassant(move_) != 0 {
-    //         return 0;
-    //     }
-    //     let promoted = promoted(move_);
-    //     if promoted != 0 {
-    //         return PIECE_VALUE[(promoted%6) as usize] as u32;
-    //     }
-    //     let target = target(move_);
-    //     let captured = position.get_piece(target(move_));
-    //     let attackers = position.get_attackers(target as usize, position.side);
-    //     let defenders = position.get_attackers(target as usize, position.side^1);
-
-    //     return get_see(get_piece(move_) % 6, captured as u8, attackers, defenders) as u32;
-    // }
-    
-    pub fn score_move(&mut self, position: &Position, move_: u32) -> u32 {
-        // if move scoring is allowed
-        if self.score_pv {
-            // make sure we are dealing with PV move
-            if self.pv_table[0][self.ply as usize] == move_ {
-                // disable score PV flag
-                self.score_pv = false;
-                // give PV move the highest score so we search it first
-                return 16000;
-            }
-        }
-
-        let mut score: u32 = 0;
+    pub fn score_move(&self, position: &Position, move_: u32) -> u32 {
         let promoted = promoted(move_);
         let target = target(move_);
+        // promotions always first
+        if promoted != 0 {
+            return PROMOTION_SCORE + PIECE_VALUE[(promoted%6) as usize] as u32 + if capture(move_) != 0 { 500 } else { 0 };
+        }
         // score capture move
         if capture(move_) != 0 {
-            // prioritize captures
-            score += 8000;
-            if enpassant(move_) != 0 {
-                // we know the target and source piece are pawns so no need to continue
-                return score;
-            } if promoted != 0 {
-                // promotions always first
-                return score+2000+PIECE_VALUE[(promoted%6) as usize] as u32;
-            }
-
-            // let captured = position.get_piece(target);
-            // let attackers = position.get_attackers(target as usize, position.side);
-            // let defenders = position.get_attackers(target as usize, position.side^1);
-            // score move by MVV LVA lookup [source piece][target piece]
-            return score + MVV_LVA[(get_piece(move_)%6) as usize][position.get_square_piece(target as usize)%6] as u32;
-            // return score + get_see(get_piece(move_) % 6, captured as u8, attackers, defenders) as u32;
+            // score move by MVV LVA lookup [source piece][target piece], capture history breaks the ties
+            let mvv_lva = MVV_LVA[(get_piece(move_)%6) as usize][position.get_square_piece(target as usize)%6] as i32;
+            return (CAPTURE_SCORE as i32 + mvv_lva * MVV_LVA_SCALE + self.capture_history(position, move_)) as u32;
         }
         // score quiet move
-        else {
-            if self.killers[0][self.ply as usize] == move_ {
-                // score 1st killer move
-                score += 4000;
-            } else if self.killers[1][self.ply as usize] == move_ {
-                // score 2nd killer move
-                score += 2500;
-            } else {
-                // score history move
-                score += self.history[get_piece(move_) as usize][target as usize] as u32;
-            }
-
-            // reward for castling
-            if castling(move_) != 0 {
-                score += 400;
-            }
-        }
-        
-        if promoted != 0 {
-            // promotions always first
-            score += 9500 + PIECE_VALUE[(promoted%6) as usize] as u32;
+        let mut score = QUIET_SCORE as i32;
+        if self.killers[0][self.ply as usize] == move_ {
+            // score 1st killer move
+            score += 100000;
+        } else if self.killers[1][self.ply as usize] == move_ {
+            // score 2nd killer move
+            score += 90000;
+        } else if self.counter_move() == move_ {
+            // score counter move
+            score += 60000;
         }
+        // score history move
+        score += self.quiet_history(position.side, move_);
 
-        return score;
+        // reward for castling
+        if castling(move_) != 0 {
+            score += 400;
+        }
+        score as u32
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,186 @@
+use std::ptr::addr_of_mut;
+
+// search parameters, kept mutable so they can be tuned
+pub struct SearchParameters {
+    // late move reductions, reduction = base + ln(depth) * ln(moves) / divisor (both in 1/100)
+    pub lmr_base: i32,
+    pub lmr_divisor: i32,
+    pub lmr_min_moves: i32,
+    pub lmr_min_depth: i32,
+    pub lmr_history_divisor: i32,
+
+    // reverse futility pruning
+    pub rfp_depth: i32,
+    pub rfp_margin: i32,
+
+    // razoring
+    pub razor_depth: i32,
+    pub razor_margin: i32,
+
+    // late move pruning, quiets after base + depth^2 moves are skipped
+    pub lmp_depth: i32,
+    pub lmp_base: i32,
+
+    // futility pruning of quiets
+    pub futility_depth: i32,
+    pub futility_margin: i32,
+
+    // SEE pruning, quiets by margin * depth and captures by margin * depth^2
+    pub see_depth: i32,
+    pub see_quiet_margin: i32,
+    pub see_capture_margin: i32,
+
+    // internal iterative reductions, at least 2 so the children of a reduced node still have a ply left
+    pub iir_depth: i32,
+
+    // null move pruning, reduction = base + depth / divisor + (eval - beta) / eval divisor
+    pub nmp_depth: i32,
+    pub nmp_base: i32,
+    pub nmp_divisor: i32,
+    pub nmp_eval_divisor: i32,
+    pub nmp_verification_depth: i32,
+
+    // singular extensions, the hash move is singular if all other moves fail low
+    // against its score minus margin * depth / 10
+    pub se_depth: i32,
+    pub se_margin: i32,
+    pub se_double_margin: i32,
+
+    // aspiration windows, iterations from this depth search +-window around the last score
+    pub aspiration_depth: i32,
+    pub aspiration_window: i32,
+}
+
+impl SearchParameters {
+    pub const fn default() -> SearchParameters {
+        SearchParameters {
+            lmr_base: 75,
+            lmr_divisor: 225,
+            lmr_min_moves: 3,
+            lmr_min_depth: 3,
+            lmr_history_divisor: 8192,
+            rfp_depth: 8,
+            rfp_margin: 75,
+            razor_depth: 2,
+            razor_margin: 250,
+            lmp_depth: 8,
+            lmp_base: 3,
+            futility_depth: 8,
+            futility_margin: 97,
+            see_depth: 8,
+            see_quiet_margin: 60,
+            see_capture_margin: 20,
+            iir_depth: 4,
+            nmp_depth: 3,
+            nmp_base: 3,
+            nmp_divisor: 3,
+            nmp_eval_divisor: 200,
+            nmp_verification_depth: 12,
+            se_depth: 8,
+            se_margin: 20,
+            se_double_margin: 20,
+            aspiration_depth: 5,
+            aspiration_window: 25,
+        }
+    }
+}
+
+pub static mut PARAMS: SearchParameters = SearchParameters::default();
+
+// late move reductions [depth][moves searched]
+pub static mut LMR_TABLE: [[u8; 64]; 64] = [[0; 64]; 64];
+
+// fill the LMR table from the current parameters
+pub fn init_lmr_table() {
+    unsafe {
+        let base = PARAMS.lmr_base as f32 / 100.0;
+        let divisor = PARAMS.lmr_divisor as f32 / 100.0;
+        for depth in 1..64 {
+            for moves in 1..64 {
+                let reduction = base + (depth as f32).ln() * (moves as f32).ln() / divisor;
+                LMR_TABLE[depth][moves] = reduction.max(0.0) as u8;
+            }
+        }
+    }
+}
+
+// a search parameter that can be tuned through a UCI option of the same name
+pub struct Tunable {
+    pub name: &'static str,
+    pub min: i32,
+    pub max: i32,
+    // step size SPSA starts perturbing the parameter with
+    pub step: i32,
+    pub value: fn(&mut SearchParameters) -> &mut i32,
+}
+
+impl Tunable {
+    pub fn default(&self) -> i32 {
+        *(self.value)(&mut SearchParameters::default())
+    }
+
+    pub fn get(&self) -> i32 {
+        unsafe { *(self.value)(&mut *addr_of_mut!(PARAMS)) }
+    }
+
+    pub fn set(&self, value: i32) {
+        unsafe { *(self.value)(&mut *addr_of_mut!(PARAMS)) = value; }
+        // the LMR table is built from its parameters
+        if self.name.starts_with("lmr_") {
+            init_lmr_table();
+        }
+    }
+
+    // line for the "uci" command
+    pub fn uci_option(&self) -> String {
+        format!("option name {} type spin default {} min {} max {}", self.name, self.default(), self.min, self.max)
+    }
+
+    // line of an SPSA input file: name, type, value, min, max, step, learning rate
+    pub fn spsa_line(&self) -> String {
+        format!("{}, int, {}, {}, {}, {}, 0.002", self.name, self.get(), self.min, self.max, self.step)
+    }
+}
+
+#[rustfmt::skip]
+pub const TUNABLES: [Tunable; 27] = [
+    Tunable { name: "lmr_base", min: 0, max: 200, step: 10, value: |p| &mut p.lmr_base },
+    Tunable { name: "lmr_divisor", min: 100, max: 400, step: 15, value: |p| &mut p.lmr_divisor },
+    Tunable { name: "lmr_min_moves", min: 1, max: 8, step: 1, value: |p| &mut p.lmr_min_moves },
+    Tunable { name: "lmr_min_depth", min: 1, max: 6, step: 1, value: |p| &mut p.lmr_min_depth },
+    Tunable { name: "lmr_history_divisor", min: 1024, max: 32768, step: 1024, value: |p| &mut p.lmr_history_divisor },
+    Tunable { name: "rfp_depth", min: 1, max: 16, step: 1, value: |p| &mut p.rfp_depth },
+    Tunable { name: "rfp_margin", min: 20, max: 200, step: 8, value: |p| &mut p.rfp_margin },
+    Tunable { name: "razor_depth", min: 1, max: 6, step: 1, value: |p| &mut p.razor_depth },
+    Tunable { name: "razor_margin", min: 50, max: 600, step: 25, value: |p| &mut p.razor_margin },
+    Tunable { name: "lmp_depth", min: 1, max: 16, step: 1, value: |p| &mut p.lmp_depth },
+    Tunable { name: "lmp_base", min: 0, max: 10, step: 1, value: |p| &mut p.lmp_base },
+    Tunable { name: "futility_depth", min: 1, max: 16, step: 1, value: |p| &mut p.futility_depth },
+    Tunable { name: "futility_margin", min: 20, max: 250, step: 10, value: |p| &mut p.futility_margin },
+    Tunable { name: "see_depth", min: 1, max: 16, step: 1, value: |p| &mut p.see_depth },
+    Tunable { name: "see_quiet_margin", min: 0, max: 200, step: 8, value: |p| &mut p.see_quiet_margin },
+    Tunable { name: "see_capture_margin", min: 0, max: 100, step: 4, value: |p| &mut p.see_capture_margin },
+    Tunable { name: "iir_depth", min: 2, max: 10, step: 1, value: |p| &mut p.iir_depth },
+    Tunable { name: "nmp_depth", min: 1, max: 8, step: 1, value: |p| &mut p.nmp_depth },
+    Tunable { name: "nmp_base", min: 1, max: 6, step: 1, value: |p| &mut p.nmp_base },
+    Tunable { name: "nmp_divisor", min: 1, max: 8, step: 1, value: |p| &mut p.nmp_divisor },
+    Tunable { name: "nmp_eval_divisor", min: 50, max: 500, step: 20, value: |p| &mut p.nmp_eval_divisor },
+    Tunable { name: "nmp_verification_depth", min: 4, max: 32, step: 2, value: |p| &mut p.nmp_verification_depth },
+    Tunable { name: "se_depth", min: 4, max: 16, step: 1, value: |p| &mut p.se_depth },
+    Tunable { name: "se_margin", min: 5, max: 60, step: 3, value: |p| &mut p.se_margin },
+    Tunable { name: "se_double_margin", min: 0, max: 100, step: 5, value: |p| &mut p.se_double_margin },
+    Tunable { name: "aspiration_depth", min: 1, max: 16, step: 1, value: |p| &mut p.aspiration_depth },
+    Tunable { name: "aspiration_window", min: 5, max: 200, step: 5, value: |p| &mut p.aspiration_window },
+];
+
+// look a tunable up by its (case insensitive) name
+pub fn find_tunable(name: &str) -> Option<&'static Tunable> {
+    TUNABLES.iter().find(|tunable| tunable.name.eq_ignore_ascii_case(name))
+}
+
+// print the SPSA input file for all the tunables
+pub fn print_spsa() {
+    for tunable in TUNABLES.iter() {
+        send!("{}", tunable.spsa_line());
+    }
+}
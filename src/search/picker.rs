@@ -0,0 +1,149 @@
+use crate::board::position::*;
+use crate::r#move::*;
+use crate::search::*;
+
+// stages of the move picker, in the order they are tried
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stage {
+    HashMove,
+    GenerateCaptures,
+    GoodCaptures,
+    FirstKiller,
+    SecondKiller,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+// hands out the moves of a position one at a time, only generating quiets once the captures didn't cut off
+pub struct MovePicker {
+    pub stage: Stage,
+    pub skip_quiets: bool,
+    hash_move: u32,
+    killers: [u32; 2],
+
+    move_list: MoveList,
+    move_scores: [u32; 256],
+    index: usize,
+    // captures that seem to lose material and under-promotions, tried after the quiets
+    bad_captures: MoveList,
+    bad_index: usize,
+}
+
+impl MovePicker {
+    // the hash move has to be legal, `decode_move` and `pv_move` only give back legal moves
+    pub fn new(hash_move: u32, killers: [u32; 2]) -> MovePicker {
+        MovePicker {
+            stage: Stage::HashMove,
+            skip_quiets: false,
+            hash_move,
+            killers,
+            move_list: MoveList::new(),
+            move_scores: [0; 256],
+            index: 0,
+            bad_captures: MoveList::new(),
+            bad_index: 0,
+        }
+    }
+
+    // get the next move to search, 0 when there are none left
+    pub fn next(&mut self, position: &Position, searcher: &Searcher) -> u32 {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    if self.hash_move != 0 {
+                        return self.hash_move;
+                    }
+                }
+                Stage::GenerateCaptures => {
+                    self.generate(position, searcher, GenType::Captures);
+                    // under-promotions aren't quiet moves, so they are tried with the bad captures instead of getting skipped with the quiets
+                    position.generate_under_promotions(&mut self.bad_captures);
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => {
+                    while let Some(move_) = self.pick_best(searcher) {
+                        if move_ == self.hash_move {
+                            continue;
+                        }
+                        if is_bad_capture(position, move_) {
+                            self.bad_captures.add(Move(move_));
+                            continue;
+                        }
+                        return move_;
+                    }
+                    self.stage = Stage::FirstKiller;
+                }
+                Stage::FirstKiller | Stage::SecondKiller => {
+                    let killer = self.killers[if self.stage == Stage::FirstKiller { 0 } else { 1 }];
+                    self.stage = if self.stage == Stage::FirstKiller { Stage::SecondKiller } else { Stage::GenerateQuiets };
+                    // killers come from sibling nodes, so they have to be checked too
+                    if !self.skip_quiets && killer != self.hash_move && capture(killer) == 0 && promoted(killer) == 0 && position.is_legal_move(killer) {
+                        return killer;
+                    }
+                }
+                Stage::GenerateQuiets => {
+                    if self.skip_quiets {
+                        self.stage = Stage::BadCaptures;
+                        continue;
+                    }
+                    self.generate(position, searcher, GenType::Quiets);
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => {
+                    if !self.skip_quiets {
+                        while let Some(move_) = self.pick_best(searcher) {
+                            // under-promotions come with the bad captures
+                            if move_ == self.hash_move || move_ == self.killers[0] || move_ == self.killers[1] || promoted(move_) != 0 {
+                                continue;
+                            }
+                            return move_;
+                        }
+                    }
+                    self.stage = Stage::BadCaptures;
+                }
+                Stage::BadCaptures => {
+                    while self.bad_index < self.bad_captures.count as usize {
+                        let move_ = self.bad_captures.moves[self.bad_index].0;
+                        self.bad_index += 1;
+                        // the under-promotions haven't been checked against the hash move yet
+                        if move_ != self.hash_move {
+                            return move_;
+                        }
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => {
+                    return 0;
+                }
+            }
+        }
+    }
+
+    // generate and score the moves of the next stage
+    fn generate(&mut self, position: &Position, searcher: &Searcher, gen_type: GenType) {
+        self.move_list = MoveList::new();
+        position.generate_moves(gen_type, &mut self.move_list);
+        for count in 0..self.move_list.count as usize {
+            self.move_scores[count] = searcher.score_move(position, self.move_list.moves[count].0);
+        }
+        self.index = 0;
+    }
+
+    // selection sort, only the moves we actually get to are sorted
+    fn pick_best(&mut self, searcher: &Searcher) -> Option<u32> {
+        if self.index >= self.move_list.count as usize {
+            return None;
+        }
+        let move_ = searcher.sort_next_move(&mut self.move_list.moves, &mut self.move_scores, self.index, self.move_list.count as usize);
+        self.index += 1;
+        Some(move_.0)
+    }
+}
+
+// a capture that loses material in the exchange
+fn is_bad_capture(position: &Position, move_: u32) -> bool {
+    !position.see_ge(move_, 0)
+}
@@ -1,12 +1,17 @@
 use crate::board::position::*;
 use crate::r#move::encode::*;
 use crate::r#move::movegen::*;
-use crate::evaluation::*;
+use crate::r#move::generate::*;
 use crate::cache::*;
-use std::mem::MaybeUninit;
+use crate::search::history::*;
+use crate::search::parameters::*;
+use crate::search::picker::*;
+use crate::search::skill::*;
+use crate::search::time::*;
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
+use std::ptr::{addr_of, addr_of_mut};
 
 pub const MAX_PLY: usize = 127;
 pub const INFINITY: i16 = 32000;
@@ -14,15 +19,31 @@ pub const MATE_VALUE: i16 = INFINITY-150;
 pub const MATE_SCORE: i16 = INFINITY-300;
 pub const NO_ENTRY: i16 = INFINITY-500;
 pub const TIME_UP: i16 = 32000+500;
+// how often the clock gets checked, must be a power of two
+pub const CHECK_TIME_NODES: u64 = 2048;
+// the root move being searched is only reported once the search takes longer than this (ms)
+pub const CURRMOVE_TIME: u128 = 3000;
 
 pub static mut OPTIONS: SearchOptions = SearchOptions::default();
-// stop search if time is up
-pub static mut STOP: bool = false;
+// stop search if time is up, set by the UCI thread on "stop"
+pub static STOP: AtomicBool = AtomicBool::new(false);
+// searching on the opponent's time, cleared on "ponderhit"
+pub static PONDER: AtomicBool = AtomicBool::new(false);
+// a search started by the UCI loop hasn't sent its bestmove yet
+pub static SEARCHING: AtomicBool = AtomicBool::new(false);
+// send diagnostics as "info string", set by the "debug" command
+pub static DEBUG: AtomicBool = AtomicBool::new(false);
 
 pub struct SearchOptions {
     pub threads_automatic: bool,
     pub threads: u16,
     pub hash_size: u16,
+    pub ponder: bool,
+    pub multipv: u16,
+    pub move_overhead: u16,
+    pub skill_level: u8,
+    pub limit_strength: bool,
+    pub elo: u16,
 }
 
 impl SearchOptions {
@@ -31,10 +52,23 @@ impl SearchOptions {
             threads_automatic: true,
             hash_size: 32,
             threads: 1,
+            ponder: false,
+            multipv: 1,
+            move_overhead: 10,
+            skill_level: MAX_SKILL_LEVEL,
+            limit_strength: false,
+            elo: Skill::max_elo() as u16,
         }
     }
 }
 
+// a fully searched root line, used for MultiPV
+#[derive(Clone)]
+pub struct RootLine {
+    pub score: i16,
+    pub pv: Vec<u32>,
+}
+
 pub static mut TT: TranspositionTable = TranspositionTable {
     table: vec![],
     size: 0,
@@ -45,26 +79,39 @@ pub static mut TT: TranspositionTable = TranspositionTable {
 pub struct Searcher {
     pub ply: u8,
     pub nodes: u64,
-    pub time: u128,
+    pub stopped: bool,
 
     pub killers: [[u32;MAX_PLY];2],
-    pub history: [[u32;64];12],
+    pub history: [[[i16;64];64];2],                // butterfly history [side][source][target]
+    pub continuation_history: Vec<i16>,            // allocated when the search starts, see CONTINUATION_SIZE
+    pub capture_history: [[[i16;6];64];12],        // [piece][target][captured piece type]
+    pub counter_moves: [[u32;64];12],              // refutations [previous piece][previous target]
+    pub move_stack: [u32;MAX_PLY],                 // moves made at each ply, 0 for null moves
+    pub excluded_moves: [u32;MAX_PLY],             // move left out at each ply by the singular extension search
+    pub extensions: [u8;MAX_PLY+1],                // plies extended on the path to each ply
+    pub root_depth: u8,
+    pub seldepth: u8,
+    pub main_thread: bool,                         // reports "currmove", false for the helper threads
+    pub researches: u32,                           // aspiration re-searches in the current iteration
 
     pub pv_table: [[u32;MAX_PLY];MAX_PLY], // PV table [ply][ply]
     pub pv_length: [u8;MAX_PLY],           // PV lenght [ply]
     pub follow_pv: bool,
-    pub score_pv: bool,
+    pub root_excluded: Vec<u32>, // root moves already reported on earlier MultiPV lines
 
-    pub full_depth_moves: u8,
-    pub reduction_limit: u8,
 
     // uci options
-    pub inc: i32,
-    pub movetime: i32,
-    pub movestogo: i32,
-    pub playtime: i32,
-    pub timeset: bool,
-    pub stoptime: u128,
+    pub time_manager: TimeManager,
+    pub ponder: bool,
+    pub infinite: bool,
+    pub node_limit: u64,
+    pub mate: u8,
+    pub search_moves: Vec<u32>, // restrict the root to these moves if not empty
+
+    // playing below full strength, see `Skill::apply`
+    pub skill: Option<Skill>,
+    pub eval_noise: i16,
+    pub noise_seed: u64,
 }
 
 impl Searcher {
@@ -72,21 +119,32 @@ impl Searcher {
         Searcher {
             ply: 0,
             nodes: 0,
-            time: 0,
+            stopped: false,
             killers: [[0;MAX_PLY];2],
-            history: [[0;64];12],
+            history: [[[0;64];64];2],
+            continuation_history: Vec::new(),
+            capture_history: [[[0;6];64];12],
+            counter_moves: [[0;64];12],
+            move_stack: [0;MAX_PLY],
+            excluded_moves: [0;MAX_PLY],
+            extensions: [0;MAX_PLY+1],
+            root_depth: 0,
+            seldepth: 0,
+            main_thread: true,
+            researches: 0,
             pv_table: [[0;MAX_PLY];MAX_PLY],
             pv_length: [0;MAX_PLY],
             follow_pv: false,
-            score_pv: false,
-            full_depth_moves: 3,
-            reduction_limit: 2,
-            inc: 0,
-            movetime: -1,
-            movestogo: 30,
-            playtime: -1,
-            timeset: false,
-            stoptime: 0,
+            root_excluded: Vec::new(),
+            time_manager: TimeManager::new(),
+            ponder: false,
+            infinite: false,
+            node_limit: 0,
+            mate: 0,
+            search_moves: Vec::new(),
+            skill: None,
+            eval_noise: 0,
+            noise_seed: 0,
         }
     }
 
@@ -105,87 +163,185 @@ impl Searcher {
     // }
 
     pub fn stop_search(&mut self) -> bool {
-        if unsafe { STOP } || (self.timeset && SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() > self.stoptime) {
+        // reading the clock is expensive, only do it every few nodes
+        self.check_stop(self.nodes & (CHECK_TIME_NODES - 1) == 0)
+    }
+
+    // like `stop_search`, `read_clock` decides whether the hard time limit is checked as well
+    fn check_stop(&mut self, read_clock: bool) -> bool {
+        if self.stopped {
+            return true;
+        }
+        if STOP.load(Ordering::Relaxed) {
+            self.stopped = true;
+            return true;
+        }
+        if self.ponder {
+            // keep searching until the GUI tells us whether the opponent played the expected move
+            if PONDER.load(Ordering::Relaxed) {
+                return false;
+            }
+            // ponderhit, the clock starts now
+            self.ponder = false;
+            self.time_manager.ponderhit();
+        }
+        if self.node_limit != 0 && self.nodes >= self.node_limit {
+            self.stopped = true;
+            return true;
+        }
+        if read_clock && self.time_manager.hard_stop() {
+            self.stopped = true;
             return true;
         }
         return false;
     }
 
-    pub fn search_position(&'static mut self, position: &'static mut Position, depth: u8) {
-        // reset search variables
-        unsafe { STOP = false; }
+    pub fn search_position(&mut self, position: &mut Position, depth: u8) {
+        // reset search variables, STOP is cleared by whoever started the search so an early "stop" isn't lost
+        self.stopped = false;
+        // the history tables are kept across the iterations of this search
+        if self.continuation_history.is_empty() {
+            self.continuation_history = vec![0; CONTINUATION_SIZE];
+        }
+
+        // never search more lines than there are legal moves
+        let mut root_moves = position.count_legal_moves();
+        if !self.search_moves.is_empty() {
+            root_moves = root_moves.min(self.search_moves.len());
+        }
+        // lines the GUI asked for, a lower skill level searches more to pick its move from but only reports these
+        let shown_lines = unsafe { OPTIONS.multipv } as usize;
+        let mut multipv = shown_lines;
+        if self.skill.is_some() {
+            multipv = multipv.max(SKILL_MULTIPV);
+        }
+        let multipv = multipv.min(root_moves).max(1);
+        // lines of the last fully searched depth, best line first
+        let mut lines: Vec<RootLine> = Vec::with_capacity(multipv);
+        // nodes of the last iteration, for the branching factor
+        let mut previous_nodes = 0;
 
         for current_depth in 1..depth+1 {
             // break if time is up
             if self.stop_search() {
                 break;
             }
-            // enable follow PV flag
-            self.follow_pv = true;
 
-            // SMP search
-            let mut score = -INFINITY;
-            let threads = unsafe { OPTIONS.threads };
-
-            // multi-threaded search if allowed
-            if unsafe { OPTIONS.threads_automatic } && current_depth > 6 {
-                let mut move_list = MoveList::new();
-                position.generate_pseudo_moves(&mut move_list);
-                let mut threads = Vec::with_capacity(move_list.count as usize);
-                let mut handles = Vec::with_capacity(move_list.count as usize);
-                for _ in 0..(move_list.count as f32/1.5) as usize {
-                    let mut pos = position.clone();
-                    let mut searcher = self.clone();
-                    let handle = thread::spawn(move || {
-                        let scorer: i16 = searcher.negamax(&mut pos, -INFINITY, INFINITY, current_depth-1, false);
-                        return (scorer, searcher.pv_length[0], searcher.pv_table[0], searcher.nodes);
-                    });
-                    handles.push(handle);
-                }
-                while !handles.is_empty() {
-                    threads.push(handles.pop().expect("error while popping").join().unwrap());
-                }
-                for item in threads.iter() {
-                    if item.0 > score {
-                        self.nodes = item.3;
-                        score = item.0;
-                        self.pv_length[0] = item.1;
-                        self.pv_table[0] = item.2;
+            let mut current_lines: Vec<RootLine> = Vec::with_capacity(multipv);
+            self.root_excluded.clear();
+            self.root_depth = current_depth;
+            self.seldepth = 0;
+            self.researches = 0;
+            let iteration_nodes = self.nodes;
+
+            for pv_index in 0..multipv {
+                // search a window around the score this line had in the last iteration
+                let params = unsafe { &*addr_of!(PARAMS) };
+                let mut delta = params.aspiration_window;
+                let (mut alpha, mut beta) = match lines.get(pv_index) {
+                    Some(line) if current_depth as i32 >= params.aspiration_depth && line.score.abs() < MATE_SCORE => {
+                        ((line.score as i32 - delta).max(-INFINITY as i32) as i16, (line.score as i32 + delta).min(INFINITY as i32) as i16)
+                    },
+                    _ => (-INFINITY, INFINITY),
+                };
+
+                let mut score;
+                loop {
+                    // follow this line's PV from the previous iteration
+                    if let Some(line) = lines.get(pv_index) {
+                        self.pv_table[0][..line.pv.len()].copy_from_slice(&line.pv);
+                        self.pv_length[0] = line.pv.len() as u8;
                     }
+                    // enable follow PV flag
+                    self.follow_pv = true;
+
+                    score = self.search_root(position, current_depth, alpha, beta);
+                    // an interrupted search says nothing about the window, and TIME_UP would pass for a fail-high
+                    if score.abs() == TIME_UP {
+                        self.stopped = true;
+                    }
+                    if self.stop_search() {
+                        break;
+                    }
+
+                    // widen the window on the side the score fell out of and search again
+                    if score <= alpha {
+                        // nothing beat alpha, so there is no PV to report the upperbound with
+                        beta = ((alpha as i32 + beta as i32) / 2) as i16;
+                        alpha = (score as i32 - delta).max(-INFINITY as i32) as i16;
+                    } else if score >= beta {
+                        let pv = self.pv_table[0][..self.pv_length[0] as usize].to_vec();
+                        if !pv.is_empty() && pv_index < shown_lines {
+                            self.print_line(pv_index + 1, &RootLine { score: beta, pv }, current_depth, "lowerbound");
+                        }
+                        beta = (score as i32 + delta).min(INFINITY as i32) as i16;
+                    } else {
+                        break;
+                    }
+                    self.researches += 1;
+                    delta += delta / 2;
                 }
-            } else if threads != 1 {
-                let mut threader = Vec::with_capacity(threads as usize);
-                let mut handles = Vec::with_capacity(threads as usize);
-                for _ in 0..threads as usize {
-                    let mut pos = position.clone();
-                    let mut searcher = self.clone();
-                    let handle = thread::spawn(move || {
-                        let scorer: i16 = searcher.negamax(&mut pos, -INFINITY, INFINITY, current_depth-1, false);
-                        return (scorer, searcher.pv_length[0], searcher.pv_table[0], searcher.nodes);
-                    });
-                    handles.push(handle);
-                }
-                while !handles.is_empty() {
-                    threader.push(handles.pop().expect("error while popping").join().unwrap());
+
+                // break if time is up
+                if self.stop_search() {
+                    break;
                 }
-                for item in threader.iter() {
-                    if item.0 > score {
-                        self.nodes = item.3;
-                        score = item.0;
-                        self.pv_length[0] = item.1;
-                        self.pv_table[0] = item.2;
-                    }
+
+                let line = RootLine {
+                    score,
+                    pv: self.pv_table[0][..self.pv_length[0] as usize].to_vec(),
+                };
+                if line.pv.is_empty() {
+                    break;
                 }
-            } else {
-                // find best move within a given position
-                score = self.negamax(position, -INFINITY, INFINITY, current_depth, true);
+                // exclude the move from the root so the next line finds the next best one
+                self.root_excluded.push(line.pv[0]);
+                current_lines.push(line);
             }
 
-            // break if time is up
+            // break if time is up, keeping the best line if it got fully searched at this depth
             if self.stop_search() {
+                if !current_lines.is_empty() {
+                    lines.retain(|line| line.pv[0] != current_lines[0].pv[0]);
+                    lines.insert(0, current_lines.swap_remove(0));
+                }
                 break;
             }
 
+            // later lines can fail higher than earlier ones, so sort before reporting
+            current_lines.sort_by(|a, b| b.score.cmp(&a.score));
+            for (pv_index, line) in current_lines.iter().enumerate().take(shown_lines) {
+                self.print_line(pv_index + 1, line, current_depth, "");
+            }
+            lines = current_lines;
+
+            if DEBUG.load(Ordering::Relaxed) {
+                let nodes = self.nodes - iteration_nodes;
+                send!("info string depth {} iteration nodes {} branching factor {:.2} aspiration re-searches {}", current_depth, nodes,
+                    nodes as f64 / previous_nodes.max(1) as f64, self.researches);
+                previous_nodes = nodes;
+            }
+
+            if let Some(best) = lines.first() {
+                // stop once a mate within the requested number of moves is proven
+                if self.mate != 0 && best.score > MATE_SCORE && best.score < MATE_VALUE
+                        && (MATE_VALUE - best.score + 1) / 2 <= self.mate as i16 {
+                    break;
+                }
+
+                // the clock only runs once pondering is over
+                if !self.ponder {
+                    self.time_manager.update(best.pv[0], best.score);
+                    // with a single legal move there is nothing to think about
+                    if self.time_manager.timeset && root_moves == 1 {
+                        break;
+                    }
+                    if self.time_manager.stop_iteration() {
+                        break;
+                    }
+                }
+            }
+            
             // if score <= alpha || score >= beta {
             //     alpha = -INFINITY;
             //     beta = INFINITY;
@@ -195,25 +351,6 @@ impl Searcher {
             // set up the window for the next iteration
             // alpha = score - 50;
             // beta = score + 50;
-            // let mate;
-            
-            if score > -MATE_VALUE && score < -MATE_SCORE {
-                print!("info score mate {} depth {} nodes {} time {} pv ", -(self.pv_length[0] as i16)/2-1, current_depth, self.nodes, SystemTime::now().duration_since(UNIX_EPOCH).expect("Time went backwards").as_millis()-self.time);
-                // mate = true;
-            } else if score > MATE_SCORE && score < MATE_VALUE {
-                print!("info score mate {} depth {} nodes {} time {} pv ", self.pv_length[0]/2+1, current_depth, self.nodes, SystemTime::now().duration_since(UNIX_EPOCH).expect("Time went backwards").as_millis()-self.time);
-                // mate = true;
-            } else {
-                print!("info score cp {} depth {} nodes {} time {} pv ", score, current_depth, self.nodes, SystemTime::now().duration_since(UNIX_EPOCH).expect("Time went backwards").as_millis()-self.time);
-                // mate = false;
-            }
-            // loop over the moves within a PV lone
-            for count in 0..self.pv_length[0] {
-                // print PV move
-                Move(self.pv_table[0][count as usize]).show();
-                print!(" ");
-            }
-            println!();
 
             // if forced mate exists there is no need to search further
             // if mate {
@@ -221,54 +358,225 @@ impl Searcher {
             // }
         }
 
+        // a lower skill level doesn't always play the best line
+        if let Some(skill) = self.skill {
+            if lines.len() > 1 {
+                let line = lines.remove(skill.pick_line(&lines));
+                lines.insert(0, line);
+            }
+        }
+
+        // the best line is what we play
+        if let Some(line) = lines.first() {
+            self.pv_table[0][..line.pv.len()].copy_from_slice(&line.pv);
+            self.pv_length[0] = line.pv.len() as u8;
+        } else {
+            // stopped before the first iteration finished, any legal move beats sending a null move
+            let mut move_list = MoveList::new();
+            position.generate_moves(GenType::All, &mut move_list);
+            let fallback = move_list.as_slice().iter().map(|move_| move_.0)
+                .find(|move_| self.search_moves.is_empty() || self.search_moves.contains(move_));
+            if let Some(move_) = fallback {
+                self.pv_table[0][0] = move_;
+                self.pv_length[0] = 1;
+            }
+        }
+        self.root_excluded.clear();
+
+        // while pondering or in infinite mode the bestmove can only be sent after "ponderhit" or "stop"
+        while (PONDER.load(Ordering::Relaxed) || self.infinite) && !STOP.load(Ordering::Relaxed) {
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+
         // bestmove
-        print!("bestmove ");
-        Move(self.pv_table[0][0]).show();
-        println!();
+        let mut output = format!("bestmove {}", Move(self.pv_table[0][0]));
+        // ponder move
+        let ponder_move = self.ponder_move(position);
+        if ponder_move != 0 {
+            output.push_str(&format!(" ponder {}", Move(ponder_move)));
+        }
 
         // age TT
         unsafe {
-            TT.age();
+            (*addr_of_mut!(TT)).age();
         }
+
+        // sending the bestmove is the last thing the search does, the GUI may send the next "go" right away
+        SEARCHING.store(false, Ordering::Relaxed);
+        send!("{}", output);
     }
 
-    pub fn enable_pv_scoring(&mut self, move_list: &MoveList) {
-        // disable following PV
-        self.follow_pv = false;
-        
-        // loop over the moves within a move list
-        for count in 0..move_list.count {
-            // make sure we hit PV move
-            if self.pv_table[0][self.ply as usize] == move_list.moves[count as usize] {
-                // enable move scoring
-                self.score_pv = true;
-                // enable following PV
-                self.follow_pv = true;
-                // break loop
-                break;
+    // search the root at the given depth, leaving the best line in `pv_table[0]`
+    fn search_root(&mut self, position: &mut Position, current_depth: u8, alpha: i16, beta: i16) -> i16 {
+        self.extensions[0] = 0;
+        // SMP search
+        let mut score = -INFINITY;
+        let threads = unsafe { OPTIONS.threads };
+        let automatic = unsafe { OPTIONS.threads_automatic } && current_depth > 6;
+
+        // multi-threaded search if allowed
+        if automatic {
+            let mut move_list = MoveList::new();
+            position.generate_moves(GenType::All, &mut move_list);
+            let mut threads = Vec::with_capacity(move_list.count as usize);
+            let mut handles = Vec::with_capacity(move_list.count as usize);
+            for index in 0..(move_list.count as f32/1.5) as usize {
+                let mut pos = position.clone();
+                let mut searcher = self.clone();
+                // only one thread reports what it is searching
+                searcher.main_thread = self.main_thread && index == 0;
+                let handle = thread::spawn(move || {
+                    let scorer: i16 = searcher.negamax(&mut pos, alpha, beta, current_depth-1, false, false);
+                    (scorer, searcher.pv_length[0], searcher.pv_table[0], searcher.nodes, searcher.seldepth, searcher.stopped)
+                });
+                handles.push(handle);
+            }
+            while !handles.is_empty() {
+                threads.push(handles.pop().expect("error while popping").join().unwrap());
+            }
+            for item in threads.iter() {
+                self.seldepth = self.seldepth.max(item.4);
+                // a helper that stopped early returns TIME_UP or a partial result, neither is a real score
+                if item.5 {
+                    self.stopped = true;
+                    continue;
+                }
+                if item.0 > score {
+                    self.nodes = item.3;
+                    score = item.0;
+                    self.pv_length[0] = item.1;
+                    self.pv_table[0] = item.2;
+                }
+            }
+        } else if threads != 1 {
+            let mut threader = Vec::with_capacity(threads as usize);
+            let mut handles = Vec::with_capacity(threads as usize);
+            for index in 0..threads as usize {
+                let mut pos = position.clone();
+                let mut searcher = self.clone();
+                // only one thread reports what it is searching
+                searcher.main_thread = self.main_thread && index == 0;
+                let handle = thread::spawn(move || {
+                    let scorer: i16 = searcher.negamax(&mut pos, alpha, beta, current_depth-1, false, false);
+                    (scorer, searcher.pv_length[0], searcher.pv_table[0], searcher.nodes, searcher.seldepth, searcher.stopped)
+                });
+                handles.push(handle);
+            }
+            while !handles.is_empty() {
+                threader.push(handles.pop().expect("error while popping").join().unwrap());
             }
+            for item in threader.iter() {
+                self.seldepth = self.seldepth.max(item.4);
+                // a helper that stopped early returns TIME_UP or a partial result, neither is a real score
+                if item.5 {
+                    self.stopped = true;
+                    continue;
+                }
+                if item.0 > score {
+                    self.nodes = item.3;
+                    score = item.0;
+                    self.pv_length[0] = item.1;
+                    self.pv_table[0] = item.2;
+                }
+            }
+        } else {
+            // find best move within a given position
+            score = self.negamax(position, alpha, beta, current_depth, true, false);
+        }
+
+        // the helpers ran on their own clocks, this thread may not have read its clock for a while
+        if (automatic || threads != 1) && self.check_stop(true) {
+            return TIME_UP;
+        }
+
+        score
+    }
+
+    // print a single "info" line for the given PV, `bound` is "lowerbound" when the score is only a bound
+    fn print_line(&self, pv_index: usize, line: &RootLine, depth: u8, bound: &str) {
+        let time = self.time_manager.search_time();
+        let score = line.score;
+        let mut output = if score > -MATE_VALUE && score < -MATE_SCORE {
+            format!("info multipv {} score mate {}", pv_index, -(line.pv.len() as i16)/2-1)
+        } else if score > MATE_SCORE && score < MATE_VALUE {
+            format!("info multipv {} score mate {}", pv_index, line.pv.len()/2+1)
+        } else {
+            format!("info multipv {} score cp {}", pv_index, score)
+        };
+        if !bound.is_empty() {
+            output.push_str(&format!(" {}", bound));
         }
+        output.push_str(&format!(" depth {} seldepth {} nodes {} nps {} hashfull {} time {} pv ", depth, self.seldepth, self.nodes,
+            self.nodes as u128 * 1000 / time.max(1), unsafe { (*addr_of!(TT)).hashfull() }, time));
+        // loop over the moves within a PV line
+        for move_ in line.pv.iter() {
+            // add PV move
+            output.push_str(&format!("{} ", Move(*move_)));
+        }
+        // the whole line is sent at once so it can't be interleaved with other output
+        send!("{}", output);
     }
 
-    pub fn quiescence(&mut self, position: &mut Position, mut alpha: i16, beta: i16) -> i16 {
-        // increment nodes
-        self.nodes += 1;
+    // get the expected reply to the best move, either from the PV or from the TT
+    pub fn ponder_move(&self, position: &mut Position) -> u32 {
+        let best_move = self.pv_table[0][0];
+        if best_move == 0 {
+            return 0;
+        }
+        if self.pv_length[0] >= 2 {
+            return self.pv_table[0][1];
+        }
+
+        // PV got cut short (e.g. by a TT cutoff), look the reply up in the TT
+        let mut ponder_move = 0;
+        if position.make(best_move) {
+            // decoding fails if the TT move isn't legal here, it might come from a hash collision
+            ponder_move = position.decode_move(unsafe { (*addr_of!(TT)).get_move(position.hash) }).0;
+        }
+        position.unmake(best_move);
 
-        // evaluate position
-        let eval = evaluate(position);
+        ponder_move
+    }
+
+    // get the move to follow the PV line of the previous iteration with
+    pub fn pv_move(&mut self, position: &Position) -> u32 {
+        // disable following PV
+        self.follow_pv = false;
 
-        // fail-hard beta cutoff
-        if eval >= beta {
-            // node (move) fails high
-            return beta;
-        } else if eval > alpha {
-            // PV node (move)
-            alpha = eval;
+        let pv_move = self.pv_table[0][self.ply as usize];
+        if position.is_legal_move(pv_move) {
+            // keep following the PV
+            self.follow_pv = true;
+            return pv_move;
         }
+        0
+    }
+
+    // quiescence search, `checks` also searches quiet checks (only done at the first ply)
+    pub fn quiescence(&mut self, position: &mut Position, mut alpha: i16, beta: i16, checks: bool) -> i16 {
+        // increment nodes
+        self.nodes += 1;
 
         // too deep, return eval
         if self.ply >= MAX_PLY as u8 {
-            return eval;
+            return self.static_eval(position);
+        }
+        self.seldepth = self.seldepth.max(self.ply);
+
+        // in check we can't stand pat, every evasion has to be searched
+        let in_check = position.checkers() != 0;
+        if !in_check {
+            // evaluate position
+            let eval = self.static_eval(position);
+
+            // fail-hard beta cutoff
+            if eval >= beta {
+                // node (move) fails high
+                return beta;
+            } else if eval > alpha {
+                // PV node (move)
+                alpha = eval;
+            }
         }
 
         // check if time is up
@@ -279,35 +587,41 @@ impl Searcher {
         // create move list
         let mut move_list = MoveList::new();
         // create score moves list
-        let mut move_scores: [u32; 256] = unsafe { MaybeUninit::uninit().assume_init() };
-        position.generate_pseudo_captures(&mut move_list);
+        let mut move_scores = [0u32; 256];
+        if in_check {
+            position.generate_moves(GenType::All, &mut move_list);
+            // checkmate
+            if move_list.count == 0 {
+                return -MATE_VALUE + self.ply as i16;
+            }
+        } else {
+            position.generate_moves(GenType::Captures, &mut move_list);
+            // quiet checks are scored below the captures, so they are searched after them
+            if checks {
+                position.generate_quiet_checks(&mut move_list);
+            }
+        }
         let counted = move_list.count;
 
         // passing score moves
         self.assign_move_scores(position, move_list.moves, &mut move_scores, counted as usize);
 
-        // sort moves
-        // let sorted = self.sort_moves(&position, move_list);
-
-        // loop over captures within move list
+        // loop over moves within move list
         for count in 0..counted {
-            // get capture move                              
-            let move_ = self.sort_next_move(&mut move_list.moves, &mut move_scores, count as usize, counted as usize);
-
-            // check if losing too much material
-            if move_scores[count as usize] as i32 - 8000 < 0 {
-                break;
-            }
+            // get next move
+            let move_ = self.sort_next_move(&mut move_list.moves, &mut move_scores, count as usize, counted as usize).0;
 
-            if !position.make(move_) {
-                position.unmake(move_);
+            // skip moves that lose material, unless we have to get out of check
+            if !in_check && !position.see_ge(move_, 0) {
                 continue;
             }
 
+            position.make(move_);
+
             // increment ply
             self.ply += 1;
             // score current move
-            let score = -self.quiescence(position, -beta, -alpha);
+            let score = -self.quiescence(position, -beta, -alpha, false);
             // take back move
             position.unmake(move_);
             // decrement ply
@@ -332,22 +646,26 @@ impl Searcher {
         alpha
     }
 
-    pub fn negamax(&mut self, position: &mut Position, mut alpha: i16, mut beta: i16, mut depth: u8, null_move: bool) -> i16 {
-        let pv_node = beta.wrapping_sub(alpha) > 1;
-        let mut best_move = BestMove { value: 0 };
+    pub fn negamax(&mut self, position: &mut Position, mut alpha: i16, mut beta: i16, mut depth: u8, null_move: bool, cut_node: bool) -> i16 {
+        let pv_node = beta as i32 - alpha as i32 > 1;
+        let mut best_move = 0;
         let mut hash_flag = LOWER_BOUND;
 
         let mut score: i16;
         let is_root = self.ply == 0;
+        // set while searching for alternatives to the hash move, see the singular extension
+        let excluded = self.excluded_moves[self.ply as usize];
 
         // increment nodes counter
         self.nodes += 1;
 
         // too deep, return eval
         if self.ply >= MAX_PLY as u8 {
-            return evaluate(position);
+            return self.static_eval(position);
         }
 
+        self.seldepth = self.seldepth.max(self.ply);
+
         // fifty-move rule
         if position.is_fifty() {
             return 0;
@@ -358,10 +676,18 @@ impl Searcher {
         
         if !is_root {
             // repetition
-            if position.is_threefold() {
+            if position.is_repetition(self.ply as usize) {
                 return 0;
             }
 
+            // we can reach a repetition with our next move, so we get at least a draw
+            if alpha < 0 && position.has_game_cycle(self.ply as usize) {
+                alpha = 0;
+                if alpha >= beta {
+                    return alpha;
+                }
+            }
+
             // mate distance pruning
             if alpha < -MATE_VALUE {
                 alpha = -MATE_VALUE;
@@ -376,7 +702,7 @@ impl Searcher {
 
         // recursion escape condition
         if depth == 0 {
-            return self.quiescence(position, alpha, beta);
+            return self.quiescence(position, alpha, beta, true);
         }
 
         // is king in check
@@ -385,13 +711,14 @@ impl Searcher {
             }, position.side^1);
 
         // increase search depth if the king has been exposed to a check
-        if in_check {
+        if in_check && self.can_extend() {
             depth += 1;
+            self.extensions[self.ply as usize] += 1;
         }
 
-        // read hash entry
-        if !is_root && !pv_node {
-            score = unsafe { TT.probe(alpha, beta, &mut best_move, depth, self.ply, position.hash) };
+        // read hash entry, the entry doesn't hold for a search without the excluded move
+        if !is_root && !pv_node && excluded == 0 {
+            score = unsafe { (*addr_of!(TT)).probe(alpha, beta, depth, self.ply, position.hash) };
             if score != NO_ENTRY {
                 // return score
                 return score;
@@ -400,71 +727,113 @@ impl Searcher {
         
         // every 2047 nodes
         if self.stop_search() {
-            return 0;
+            return TIME_UP;
         }
 
         // static evaluation
-        let eval = evaluate(position);
+        let eval = self.static_eval(position);
+        let params = unsafe { &*addr_of!(PARAMS) };
+        let depth_i32 = depth as i32;
+
+        // search the PV move first while following the PV line, otherwise the hash move
+        let hash_move = if self.follow_pv && excluded == 0 { self.pv_move(position) } else { 0 };
+        let hash_move = if hash_move != 0 { hash_move } else { position.decode_move(unsafe { (*addr_of!(TT)).get_move(position.hash) }).0 };
 
         if !in_check && !pv_node {
-            // evaluation pruning
-            if depth < 3 && (beta-1).abs() as i32 > -49000 + 100 {
-                let eval_margin = PIECE_VALUE[Piece::WhitePawn as usize] * depth as i16;
+            // reverse futility pruning, the position is too good for the opponent to allow it
+            if depth_i32 <= params.rfp_depth && beta.abs() < MATE_SCORE {
+                let eval_margin = (params.rfp_margin * depth_i32) as i16;
                 if eval - eval_margin >= beta {
                     return eval - eval_margin;
                 }
             }
 
-            // // razoring
-            // if depth < 2 && eval + 339 < alpha {
-            //     return self.quiescence(position, alpha, beta);
-            // }
-
-            // // futility pruning
-            // let reversed_fp_margin = 64*depth as i16;
-            // if depth < 9 && eval - reversed_fp_margin >= beta {
-            //     return eval - reversed_fp_margin;
-            // }
+            // razoring, drop into quiescence when even a big margin can't reach alpha
+            if depth_i32 <= params.razor_depth && (eval as i32 + params.razor_margin * depth_i32) < alpha as i32 {
+                let score = self.quiescence(position, alpha, beta, true);
+                if score < alpha {
+                    return score;
+                }
+            }
 
             // null move pruning
-            if null_move {
-                if self.do_nmp(position, depth, eval, beta) {
-                    // increment ply
-                    self.ply += 1;
-                    // make null move
-                    position.make_null_move();
-                    // search moves with reduced depth to find beta cutoffs
-                    let score = -self.negamax(position, -beta, -beta+1, depth-1-self.reduction_limit, false);
-                    // take back null move
-                    position.unmake_null_move();
-                    // decrement ply
-                    self.ply -= 1;
-
-                    // return 0 if time is up
-                    if unsafe { STOP } { return NO_ENTRY; }
-                    // fail-hard beta cutoff
-                    if score >= beta {
+            if null_move && excluded == 0 && self.do_nmp(position, depth, eval, beta) {
+                let reduction = params.nmp_base + depth_i32 / params.nmp_divisor + ((eval as i32 - beta as i32) / params.nmp_eval_divisor).min(3);
+                let null_depth = (depth_i32 - reduction).max(0) as u8;
+
+                self.move_stack[self.ply as usize] = 0;
+                self.extensions[self.ply as usize + 1] = self.extensions[self.ply as usize];
+                // increment ply
+                self.ply += 1;
+                // make null move
+                position.make_null_move();
+                // search moves with reduced depth to find beta cutoffs
+                let score = -self.negamax(position, -beta, -beta+1, null_depth, false, !cut_node);
+                // take back null move
+                position.unmake_null_move();
+                // decrement ply
+                self.ply -= 1;
+
+                // check if time is up
+                if self.stop_search() { return TIME_UP; }
+                // fail-hard beta cutoff
+                if score >= beta {
+                    // at high depths make sure we aren't in zugzwang with a search without null moves
+                    if depth_i32 < params.nmp_verification_depth || self.negamax(position, beta-1, beta, null_depth, false, false) >= beta {
                         // node (move) fails high
                         return beta;
                     }
                 }
+            }
+        }
 
-                // razoring
-                score = eval + PIECE_VALUE[Piece::WhitePawn as usize];
-                let new_score;
+        // internal iterative reductions, without a hash move this node is probably not that important
+        if (pv_node || cut_node) && hash_move == 0 && depth_i32 >= params.iir_depth {
+            depth -= 1;
+        }
+        let depth_i32 = depth as i32;
+
+        // singular extension, if every move but the hash move fails low against a margin below
+        // its score the hash move is the only good one and gets searched deeper
+        let mut singular_extension = 0;
+        if !is_root && excluded == 0 && hash_move != 0 && depth_i32 >= params.se_depth && self.can_extend() {
+            if let Some(entry) = unsafe { (*addr_of!(TT)).get_entry(position.hash) } {
+                let (tt_score, tt_depth, tt_flag) = (entry.score, entry.depth as i32, entry.flag);
+                // the real score has to be at least the hash score, so the entry is exact or failed high
+                // (stored as UPPER_BOUND, see the flags in tt.rs), and it has to be searched deep enough
+                if entry.best_move == Move(hash_move).to_compact() && (tt_flag == EXACT || tt_flag == UPPER_BOUND) && tt_depth >= depth_i32 - 3 && tt_score.abs() < MATE_SCORE {
+                    let singular_beta = tt_score - (params.se_margin * depth_i32 / 10) as i16;
+                    let singular_depth = ((depth_i32 - 1) / 2) as u8;
+
+                    // the excluded search runs on this ply, so keep what it would overwrite
+                    let follow_pv = self.follow_pv;
+                    let extensions = self.extensions[self.ply as usize];
+                    self.excluded_moves[self.ply as usize] = hash_move;
+                    let score = self.negamax(position, singular_beta - 1, singular_beta, singular_depth, false, cut_node);
+                    self.excluded_moves[self.ply as usize] = 0;
+                    self.extensions[self.ply as usize] = extensions;
+                    self.follow_pv = follow_pv;
+
+                    if self.stop_search() {
+                        return TIME_UP;
+                    }
 
-                if score < beta {
-                    if depth == 1 {
-                        new_score = self.quiescence(position, alpha, beta);
-                        if new_score < beta {
-                            return if new_score > score { new_score } else { score };
+                    if score < singular_beta {
+                        singular_extension = 1;
+                        // far below, extend twice if the budget allows it
+                        if !pv_node && (score as i32) < singular_beta as i32 - params.se_double_margin && self.extensions[self.ply as usize] + 1 < self.root_depth {
+                            singular_extension = 2;
                         }
+                    } else if singular_beta >= beta {
+                        // multi-cut, even without the hash move another move beats beta
+                        return singular_beta;
                     }
                 }
             }
         }
+
         // nice name btw lol
-        let fp_margin = eval + 97 * depth as i16;
+        let fp_margin = eval as i32 + params.futility_margin * depth_i32;
 
         // check if time is up
         if self.stop_search() {
@@ -473,52 +842,91 @@ impl Searcher {
 
         // legal moves
         let mut legal_moves = 0;
-        let mut move_list = MoveList::new();
-        // create score moves list
-        let mut move_scores: [u32; 256] = unsafe { MaybeUninit::uninit().assume_init() };
-
-        position.generate_pseudo_moves(&mut move_list);
-        let counted = move_list.count;
-        // if we are now following PV line
-        if self.follow_pv {
-            // enable PV move scoring
-            self.enable_pv_scoring(&move_list);
-        }
-
-        // assign move scores
-        self.assign_move_scores(position, move_list.moves, &mut move_scores, counted as usize);
-        // // sort moves
-        // let sorted = self.sort_moves(&position, move_list);
+        let killers = [self.killers[0][self.ply as usize], self.killers[1][self.ply as usize]];
+        let counter_move = self.counter_move();
+        let mut picker = MovePicker::new(hash_move, killers);
 
         // number of moves searched in a move list
         let mut moves_searched = 0;
 
         let mut best_score = -INFINITY;
-        let mut skip_quiet = false;
+        // moves searched without a cutoff, they get a history malus when a later move cuts off
+        let mut quiets_tried = [0u32; 64];
+        let mut quiet_count = 0;
+        let mut captures_tried = [0u32; 32];
+        let mut capture_count = 0;
+
+        // loop over moves given by the move picker
+        loop {
+            let move_ = picker.next(position, self);
+            if move_ == 0 {
+                break;
+            }
 
-        // loop over moves within move list
-        for count in 0..counted {
-            // get move                               
-            let move_ = self.sort_next_move(&mut move_list.moves, &mut move_scores, count as usize, counted as usize);
+            // skip root moves that already have their own MultiPV line or weren't asked for by "searchmoves"
+            if is_root && (self.root_excluded.contains(&move_) || (!self.search_moves.is_empty() && !self.search_moves.contains(&move_))) {
+                continue;
+            }
+
+            // the singular extension search wants to know how the other moves do
+            if move_ == excluded {
+                continue;
+            }
 
-            let is_quiet = capture(move_) == 0;
-            if is_quiet && skip_quiet {
+            let is_quiet = capture(move_) == 0 && promoted(move_) == 0;
+            if is_quiet && picker.skip_quiets {
                 continue;
             }
 
-            let is_killer = self.killers[0][self.ply as usize] == move_ || self.killers[1][self.ply as usize] == move_;
+            let is_killer = killers.contains(&move_) || move_ == counter_move;
 
-            if !is_root && best_score > -INFINITY {
-                if depth < 8 && is_quiet && !is_killer && fp_margin <= alpha && alpha.abs() < INFINITY - 100 {
-                    skip_quiet = true;
-                    continue;
+            // pruning of late moves, only once we have a move to fall back on
+            if !is_root && best_score > -MATE_SCORE {
+                if is_quiet && !in_check && !pv_node {
+                    // late move pruning
+                    if depth_i32 <= params.lmp_depth && quiet_count as i32 >= params.lmp_base + depth_i32 * depth_i32 {
+                        picker.skip_quiets = true;
+                        continue;
+                    }
+                    // futility pruning
+                    if depth_i32 <= params.futility_depth && !is_killer && fp_margin <= alpha as i32 && alpha.abs() < MATE_SCORE {
+                        // the remaining quiets won't be generated at all
+                        picker.skip_quiets = true;
+                        continue;
+                    }
+                }
+
+                // SEE pruning
+                if depth_i32 <= params.see_depth {
+                    let threshold = if is_quiet { -params.see_quiet_margin * depth_i32 } else { -params.see_capture_margin * depth_i32 * depth_i32 };
+                    if !position.see_ge(move_, threshold as i16) {
+                        continue;
+                    }
                 }
             }
 
-            if !position.make(move_) {
-                position.unmake(move_);
-                continue;
+            let history = if is_quiet { self.quiet_history(position.side, move_) } else { 0 };
+
+            // extend singular hash moves and recaptures on the PV
+            let mut extension = 0;
+            if move_ == hash_move {
+                extension = singular_extension;
+            } else if pv_node && capture(move_) != 0 && self.can_extend() {
+                let previous = self.previous_move(1);
+                if previous != 0 && capture(previous) != 0 && target(previous) == target(move_) {
+                    extension = 1;
+                }
             }
+            let new_depth = depth - 1 + extension;
+
+            // tell the GUI which root move we're on during long searches
+            if is_root && self.main_thread && self.time_manager.search_time() > CURRMOVE_TIME {
+                send!("info depth {} currmove {} currmovenumber {}", depth, Move(move_), legal_moves + 1);
+            }
+
+            position.make(move_);
+            self.move_stack[self.ply as usize] = move_;
+            self.extensions[self.ply as usize + 1] = self.extensions[self.ply as usize] + extension;
 
             // increment ply and legal moves
             self.ply += 1;
@@ -527,23 +935,37 @@ impl Searcher {
             // full depth search
             if moves_searched == 0 {
                 // do normal alpha-beta search
-                score = -self.negamax(position, -beta, -alpha, depth - 1, true);
+                score = -self.negamax(position, -beta, -alpha, new_depth, true, !pv_node && !cut_node);
             } else {
                 // condition to consider LMR (late move reduction)
-                if moves_searched >= self.full_depth_moves && depth >= self.reduction_limit && 
-                                    !in_check && capture(move_) == 0 && promoted(move_) == 0 {
+                if moves_searched >= params.lmr_min_moves && depth_i32 >= params.lmr_min_depth && !in_check && is_quiet {
+                    let mut reduction = unsafe { LMR_TABLE[depth.min(63) as usize][(moves_searched as usize).min(63)] } as i32;
+                    // reduce more in nodes that probably fail high anyway, less in PV nodes
+                    if !pv_node {
+                        reduction += 1;
+                    }
+                    if cut_node {
+                        reduction += 1;
+                    }
+                    // moves that refuted other moves before are more interesting
+                    if is_killer {
+                        reduction -= 1;
+                    }
+                    reduction -= history / params.lmr_history_divisor;
+
                     // search current move with reduced depth
-                    score = -self.negamax(position, -alpha-1, -alpha, depth-2, true);
+                    let reduced_depth = (new_depth as i32 - reduction).clamp(1, new_depth as i32) as u8;
+                    score = -self.negamax(position, -alpha-1, -alpha, reduced_depth, true, true);
+                    // the reduced search beat alpha, search at full depth
+                    if score > alpha && reduced_depth < new_depth {
+                        score = -self.negamax(position, -alpha-1, -alpha, new_depth, true, !cut_node);
+                    }
                 } else {
-                    score = alpha+1;
+                    score = -self.negamax(position, -alpha-1, -alpha, new_depth, true, !cut_node);
                 }
-                // if found a better move during LMR re-search at normal depth
-                if score > alpha {
-                    score = -self.negamax(position, -alpha-1, -alpha, depth-1, true);
-                    // if LMR fails re-search at full depth
-                    if score > alpha && score < beta {
-                        score = -self.negamax(position, -beta, -alpha, depth-1, true);
-                    }
+                // the null window search failed high, re-search with the full window
+                if score > alpha && score < beta {
+                    score = -self.negamax(position, -beta, -alpha, new_depth, true, false);
                 }
             }
 
@@ -567,17 +989,11 @@ impl Searcher {
             // fail-hard beta cutoff
             if score > alpha {
                 hash_flag = EXACT;
-                best_move.value = move_;
+                best_move = move_;
                 best_score = score;
                 // PV node (move)
                 alpha = score;
 
-                // on quiet move
-                if is_quiet {  
-                    // store history moves
-                    self.history[get_piece(move_) as usize][target(move_) as usize] += depth as u32;
-                }
-
                 // write PV move
                 self.pv_table[self.ply as usize][self.ply as usize] = move_;
                 // loop over the next ply
@@ -589,23 +1005,43 @@ impl Searcher {
                 self.pv_length[self.ply as usize] = self.pv_length[self.ply as usize+1];
 
                 if score >= beta {
-                    // store hash entry with the score equal to beta
-                    unsafe { TT.write(position.hash, beta, best_move.value, depth, self.ply, UPPER_BOUND); }
+                    // store hash entry with the score equal to beta, unless moves were left out
+                    if excluded == 0 && (!is_root || self.root_excluded.is_empty()) {
+                        unsafe { (*addr_of_mut!(TT)).write(position.hash, beta, Move(best_move), depth, self.ply, UPPER_BOUND); }
+                    }
 
                     // on quiet moves
                     if is_quiet {
                         // store killer moves
-                        self.killers[1][self.ply as usize] = self.killers[0][self.ply as usize];
-                        self.killers[0][self.ply as usize] = move_;
+                        if self.killers[0][self.ply as usize] != move_ {
+                            self.killers[1][self.ply as usize] = self.killers[0][self.ply as usize];
+                            self.killers[0][self.ply as usize] = move_;
+                        }
+                        self.update_quiet_history(position.side, move_, &quiets_tried[..quiet_count], depth);
+                        self.update_capture_history(position, None, &captures_tried[..capture_count], depth);
+                    } else if capture(move_) != 0 {
+                        self.update_capture_history(position, Some(move_), &captures_tried[..capture_count], depth);
                     }
                     // node (move) fails high
                     return beta;
                 }
             }
+
+            // remember the move for the history maluses
+            if is_quiet && quiet_count < quiets_tried.len() {
+                quiets_tried[quiet_count] = move_;
+                quiet_count += 1;
+            } else if capture(move_) != 0 && capture_count < captures_tried.len() {
+                captures_tried[capture_count] = move_;
+                capture_count += 1;
+            }
         }
         // check if checkmate or stalemate
         if legal_moves == 0 {
-            if in_check {
+            if excluded != 0 {
+                // the excluded move was the only one
+                return alpha;
+            } else if in_check {
                 // checkmate
                 return -MATE_VALUE+self.ply as i16;
             } else {
@@ -613,21 +1049,28 @@ impl Searcher {
                 return 0;
             }
         }
-        // store hash entry with the score equal to alpha
-        unsafe { TT.write(position.hash, alpha, best_move.value, depth, self.ply, hash_flag); }
+        // store hash entry with the score equal to alpha, unless moves were left out
+        if excluded == 0 && (!is_root || self.root_excluded.is_empty()) {
+            unsafe { (*addr_of_mut!(TT)).write(position.hash, alpha, Move(best_move), depth, self.ply, hash_flag); }
+        }
         // node (move) fails low
         return alpha;
     }
 
-    fn assign_move_scores(&mut self, position: &Position, moves: [u32; 256], move_scores: &mut [u32; 256], moves_count: usize) {
+    fn assign_move_scores(&mut self, position: &Position, moves: [Move; 256], move_scores: &mut [u32; 256], moves_count: usize) {
         for move_index in 0..moves_count {
             let r#move = moves[move_index];
-            move_scores[move_index] = self.score_move(position, r#move);
+            move_scores[move_index] = self.score_move(position, r#move.0);
         }
     }
 
+    // extensions on a path are limited to the depth of the iteration, so they can't blow up the tree
+    fn can_extend(&self) -> bool {
+        self.extensions[self.ply as usize] < self.root_depth
+    }
+
     fn do_nmp(&self, position: &Position, depth: u8, eval: i16, beta: i16) -> bool {
         let has_non_pawn = (position.bitboards[0].0 | position.bitboards[Piece::WhiteKing as usize].0 | position.bitboards[Piece::BlackPawn as usize].0 | position.bitboards[Piece::BlackKing as usize].0) != (position.occupancies[0].0 | position.occupancies[1].0);
-        return self.ply != 0 && depth > 5 && eval >= beta && has_non_pawn;
+        self.ply != 0 && depth as i32 >= unsafe { PARAMS.nmp_depth } && eval >= beta && has_non_pawn
     }
 }
\ No newline at end of file
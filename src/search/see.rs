@@ -1,68 +1,92 @@
+use crate::board::attacks::*;
+use crate::board::bitboard::*;
+use crate::board::position::*;
 use crate::evaluation::*;
+use crate::r#move::encode::*;
 
-pub fn get_lsb(x: u64) -> u64 {
-    return x & x.wrapping_neg();
-}
+impl Position {
+    // static exchange evaluation, check if the exchange started by a move wins at least `threshold`
+    // both sides always recapture with their least valuable piece and can stop whenever they like
+    pub fn see_ge(&self, move_: u32, threshold: i16) -> bool {
+        // castling, en passant and promotions are only judged by the threshold
+        if castling(move_) != 0 || enpassant(move_) != 0 || promoted(move_) != 0 {
+            return 0 >= threshold;
+        }
 
-pub fn evaluate(target_piece: u8, attackers: u8, defenders: u8) -> i16 {
-    if attackers == 0 {
-        return 0;
-    }
+        let source_square = source(move_) as usize;
+        let target_square = target(move_) as usize;
 
-    return evaluate_internal(get_lsb(attackers as u64).trailing_zeros() as u8, get_see_piece_index(target_piece), attackers, defenders);
-}
+        // we win the captured piece, if it isn't enough we lose
+        let mut swap = if capture(move_) != 0 { PIECE_VALUE[self.get_square_piece(target_square) % 6] } else { 0 } - threshold;
+        if swap < 0 {
+            return false;
+        }
+        // even losing the moving piece for nothing is enough
+        swap = PIECE_VALUE[(get_piece(move_) % 6) as usize] - swap;
+        if swap <= 0 {
+            return true;
+        }
 
-pub fn evaluate_internal(attacking_piece: u8, target_piece: u8, attackers: u8, defenders: u8) -> i16 {
-    if attackers == 0 {
-        return 0;
-    }
-    
-    let score = evaluate_internal(if defenders == 0 { 0 } else { get_lsb(defenders as u64).trailing_zeros() } as u8, attacking_piece, defenders, attackers & !(1 << attacking_piece));
-    return 0.max(get_piece_value(target_piece) - score);
-}
+        let bishops_queens = self.bitboards[Piece::WhiteBishop as usize].0 | self.bitboards[Piece::BlackBishop as usize].0
+            | self.bitboards[Piece::WhiteQueen as usize].0 | self.bitboards[Piece::BlackQueen as usize].0;
+        let rooks_queens = self.bitboards[Piece::WhiteRook as usize].0 | self.bitboards[Piece::BlackRook as usize].0
+            | self.bitboards[Piece::WhiteQueen as usize].0 | self.bitboards[Piece::BlackQueen as usize].0;
+
+        let mut occupancy = (self.occupancies[Side::WHITE].0 | self.occupancies[Side::BLACK].0) ^ (1u64 << source_square);
+        let mut attackers = self.attackers_to(target_square, occupancy);
+        // pinned pieces can only recapture along the pin
+        let pinned = [self.pinned(Side::WHITE), self.pinned(Side::BLACK)];
 
-pub static mut SEE_TABLE: [[[i16; 256];256];6] = [[[0;256];256];6];
+        let mut side = self.side;
+        // 1 while the side that made the move is winning the exchange
+        let mut result = 1;
+        loop {
+            side ^= 1;
+            attackers &= occupancy;
 
-pub fn init_see() {
-    for a in 0..6 {
-        for b in 0..256 {
-            for c in 0..256 {
-                unsafe {
-                    SEE_TABLE[a][b][c] = evaluate(a as u8, b as u8, c as u8);
+            let mut side_attackers = attackers & self.occupancies[side].0;
+            let king_square = self.bitboards[Piece::WhiteKing as usize + side * 6].ls1b() as usize;
+            let mut pins = Bitboard(side_attackers & pinned[side]);
+            while pins.0 != 0 {
+                let square = pins.pop_ls1b();
+                if unsafe { LINE[king_square][square] } & (1u64 << target_square) == 0 {
+                    side_attackers &= !(1u64 << square);
                 }
             }
-        }
-    }
-}
+            if side_attackers == 0 {
+                break;
+            }
+            result ^= 1;
 
-pub fn get_see(attacking_piece: u8, target_piece: u8, attackers: u8, defenders: u8) -> i16 {
-    let attacking_piece_index = get_see_piece_index(attacking_piece);
-    let target_piece_index = get_see_piece_index(target_piece);
-    let updated_attackers = attackers & !(1 << attacking_piece_index);
+            // least valuable attacker
+            let mut piece = Piece::WhitePawn as usize;
+            while self.bitboards[piece + side * 6].0 & side_attackers == 0 {
+                piece += 1;
+            }
 
-    let see_result = unsafe { SEE_TABLE[attacking_piece as usize][defenders as usize][updated_attackers as usize] };
-    get_piece_value(target_piece_index) - see_result
-}
+            if piece == Piece::WhiteKing as usize {
+                // the king can only recapture if the other side has no attackers left
+                return (if attackers & !self.occupancies[side].0 != 0 { result ^ 1 } else { result }) != 0;
+            }
 
-fn get_see_piece_index(piece: u8) -> u8 {
-    match piece {
-        0 => 0,
-        1 => 1,
-        2 => 1,
-        3 => 4,
-        4 => 6,
-        5 => 7,
-        _ => panic!("Invalid value: piece={}", piece),
-    }
-}
+            // the piece recaptures and stands on the target square now
+            swap = PIECE_VALUE[piece] - swap;
+            if swap < result {
+                break;
+            }
+            let attacker = self.bitboards[piece + side * 6].0 & side_attackers;
+            occupancy ^= attacker & attacker.wrapping_neg();
 
-fn get_piece_value(piece_index: u8) -> i16 {
-    match piece_index {
-        0 => PIECE_VALUE[0] as i16,           // Pawn
-        1 | 2 | 3 => PIECE_VALUE[2] as i16, // 3x Knight/bishop
-        4 | 5 => PIECE_VALUE[3] as i16,       // 2x Rook
-        6 => PIECE_VALUE[4] as i16,          // Queen
-        7 => PIECE_VALUE[5] as i16,           // King
-        _ => panic!("Invalid value: piece_index={}", piece_index),
+            // sliders behind the piece that moved join in
+            unsafe {
+                if piece == Piece::WhitePawn as usize || piece == Piece::WhiteBishop as usize || piece == Piece::WhiteQueen as usize {
+                    attackers |= get_bishop_attacks(target_square, Bitboard(occupancy)) & bishops_queens;
+                }
+                if piece == Piece::WhiteRook as usize || piece == Piece::WhiteQueen as usize {
+                    attackers |= get_rook_attacks(target_square, Bitboard(occupancy)) & rooks_queens;
+                }
+            }
+        }
+        result != 0
     }
 }
@@ -0,0 +1,130 @@
+use crate::board::position::*;
+use crate::evaluation::*;
+use crate::search::*;
+
+use rand::Rng;
+
+// "Skill Level" runs from 0 to this, which is full strength
+pub const MAX_SKILL_LEVEL: u8 = 20;
+
+// rating assumed for the full strength engine, the calibration measures the levels relative to it
+pub const FULL_STRENGTH_ELO: i32 = 2400;
+
+// Elo difference to full strength of skill levels 0 to 19. These are estimates that fall off smoothly
+// with the level, not measurements, check them with e.g. "calibrate levels=0,5,10,15,19 tc=2+0.02"
+// which prints the measured difference next to the table value
+pub const SKILL_ELO: [i32; 20] = [
+    -2018, -1918, -1815, -1709, -1601, -1492, -1383, -1275, -1168, -1064,
+    -962, -864, -771, -683, -602, -527, -461, -402, -354, -315,
+];
+
+// lines searched to pick a weaker move from
+pub const SKILL_MULTIPV: usize = 4;
+
+// mixed into the root position hash to seed the eval noise
+const NOISE_SEED: u64 = 0x5851F42D4C957F2D;
+
+// a playing strength below full strength, the level can be fractional when it comes from UCI_Elo
+#[derive(Clone, Copy, Debug)]
+pub struct Skill {
+    pub level: f64,
+}
+
+impl Skill {
+    // the strength asked for by the UCI options, None at full strength
+    pub fn from_options() -> Option<Skill> {
+        let (limit_strength, elo, level) = unsafe { (OPTIONS.limit_strength, OPTIONS.elo, OPTIONS.skill_level) };
+        if limit_strength {
+            return Some(Skill::from_elo(elo as i32));
+        }
+        if level < MAX_SKILL_LEVEL {
+            return Some(Skill { level: level as f64 });
+        }
+        None
+    }
+
+    // the level that plays at the given rating, interpolated between the calibrated levels
+    pub fn from_elo(elo: i32) -> Skill {
+        let difference = elo - FULL_STRENGTH_ELO;
+        let last = SKILL_ELO.len() - 1;
+        if difference <= SKILL_ELO[0] {
+            return Skill { level: 0.0 };
+        }
+        if difference >= SKILL_ELO[last] {
+            return Skill { level: last as f64 };
+        }
+        let level = (0..last).find(|&level| difference < SKILL_ELO[level + 1]).unwrap_or(last - 1);
+        let fraction = (difference - SKILL_ELO[level]) as f64 / (SKILL_ELO[level + 1] - SKILL_ELO[level]) as f64;
+        Skill { level: level as f64 + fraction }
+    }
+
+    // the range of UCI_Elo, from level 0 to the strongest calibrated level
+    pub const fn min_elo() -> i32 {
+        FULL_STRENGTH_ELO + SKILL_ELO[0]
+    }
+
+    pub const fn max_elo() -> i32 {
+        FULL_STRENGTH_ELO + SKILL_ELO[SKILL_ELO.len() - 1]
+    }
+
+    // nodes searched per move, from 400 at level 0 to about 120k at level 19
+    pub fn node_limit(&self) -> u64 {
+        (400.0 * 1.35f64.powf(self.level)) as u64
+    }
+
+    // the most centipawns the static eval is blurred by
+    pub fn eval_noise(&self) -> i16 {
+        ((MAX_SKILL_LEVEL as f64 - self.level) * 5.0) as i16
+    }
+
+    // set up a search of the given position to play at this level
+    pub fn apply(&self, searcher: &mut Searcher, position: &Position) {
+        let node_limit = self.node_limit();
+        if searcher.node_limit == 0 || searcher.node_limit > node_limit {
+            searcher.node_limit = node_limit;
+        }
+        searcher.skill = Some(*self);
+        searcher.eval_noise = self.eval_noise();
+        // the eval noise follows from the position, so the same position is always blurred the same way
+        searcher.noise_seed = position.hash.wrapping_mul(0xD6E8FEB86659FD93) ^ NOISE_SEED;
+    }
+
+    // pick one of the searched lines (best first), the lower the level the more often a worse line gets picked
+    pub fn pick_line(&self, lines: &[RootLine]) -> usize {
+        // from 120 at level 0 down to 0 at full strength, below 128 so lines far behind the best one are never picked
+        let weakness = 6.0 * (MAX_SKILL_LEVEL as f64 - self.level);
+        let top = lines[0].score as f64;
+        let spread = (top - lines[lines.len() - 1].score as f64).min(100.0);
+        if weakness <= 0.0 {
+            return 0;
+        }
+        let mut rng = rand::thread_rng();
+
+        let mut best = 0;
+        let mut best_value = f64::MIN;
+        for (index, line) in lines.iter().enumerate() {
+            let push = (weakness * (top - line.score as f64) + spread * rng.gen_range(0.0..weakness)) / 128.0;
+            let value = line.score as f64 + push;
+            if value >= best_value {
+                best_value = value;
+                best = index;
+            }
+        }
+        best
+    }
+}
+
+impl Searcher {
+    // the static eval, blurred when playing at a lower level
+    pub fn static_eval(&self, position: &Position) -> i16 {
+        let eval = evaluate(position);
+        if self.eval_noise == 0 {
+            return eval;
+        }
+        // the same position gets the same noise for the whole search so scores stay consistent
+        let hash = (position.hash ^ self.noise_seed).wrapping_mul(0x9E3779B97F4A7C15);
+        let range = 2 * self.eval_noise as u64 + 1;
+        let noise = ((hash >> 32) % range) as i32 - self.eval_noise as i32;
+        (eval as i32 + noise).clamp(-(MATE_SCORE as i32) + 1, MATE_SCORE as i32 - 1) as i16
+    }
+}
@@ -0,0 +1,148 @@
+use std::time::Instant;
+
+// moves we expect to still play when the GUI doesn't send "movestogo"
+const DEFAULT_MOVES_TO_GO: i32 = 30;
+// the hard limit may use up to this many times the soft limit
+const HARD_LIMIT_FACTOR: u128 = 4;
+// soft limit scale by the number of iterations the best move stayed the same
+const STABILITY_SCALE: [f32; 5] = [2.2, 1.4, 1.0, 0.85, 0.75];
+// how many times longer we expect the next iteration to take than the last one
+const BRANCHING_FACTOR: u128 = 2;
+
+// milliseconds passed since the given instant, 0 if it was never set
+fn millis_since(instant: Option<Instant>) -> u128 {
+    instant.map_or(0, |instant| instant.elapsed().as_millis())
+}
+
+#[derive(Clone)]
+pub struct TimeManager {
+    // limits given by the "go" command
+    pub playtime: i32,
+    pub inc: i32,
+    pub movestogo: i32,
+    pub movetime: i32,
+
+    pub timeset: bool,
+    pub search_start: Option<Instant>,
+    pub start: Option<Instant>, // moved to "ponderhit" when pondering
+    pub soft_limit: u128, // don't start a new iteration after this
+    pub hard_limit: u128, // abort the search after this
+
+    // state of the previous iterations
+    best_move: u32,
+    stability: usize,
+    score: i16,
+    score_drop: i32,
+    iteration_start: Option<Instant>,
+    last_iteration: u128,
+}
+
+impl TimeManager {
+    pub const fn new() -> TimeManager {
+        TimeManager {
+            playtime: -1,
+            inc: 0,
+            movestogo: 0,
+            movetime: -1,
+            timeset: false,
+            search_start: None,
+            start: None,
+            soft_limit: 0,
+            hard_limit: 0,
+            best_move: 0,
+            stability: 0,
+            score: 0,
+            score_drop: 0,
+            iteration_start: None,
+            last_iteration: 0,
+        }
+    }
+
+    // calculate the soft and hard deadlines from the "go" limits
+    pub fn init(&mut self, move_overhead: u16, ponder: bool) {
+        self.search_start = Some(Instant::now());
+        self.start = self.search_start;
+        self.iteration_start = self.search_start;
+        let overhead = move_overhead as i32;
+
+        if self.movetime != -1 {
+            // fixed time per move, use all of it
+            self.timeset = true;
+            self.hard_limit = (self.movetime - overhead).max(1) as u128;
+            self.soft_limit = self.hard_limit;
+        } else if self.playtime != -1 {
+            self.timeset = true;
+            // always keep the overhead on the clock
+            let remaining = (self.playtime - overhead).max(1) as u128;
+            let moves_to_go = if self.movestogo > 0 { self.movestogo } else { DEFAULT_MOVES_TO_GO } as u128;
+
+            let mut soft = remaining / moves_to_go + self.inc as u128 * 3 / 4;
+            let mut hard = soft * HARD_LIMIT_FACTOR;
+            // the GUI is pondering for us, so we can afford to think a bit longer
+            if ponder {
+                soft += soft / 4;
+                hard += hard / 4;
+            }
+            // never plan on using more than most of what's left on the clock
+            self.hard_limit = hard.min(remaining * 3 / 4).max(1);
+            self.soft_limit = soft.min(self.hard_limit);
+        }
+    }
+
+    // the opponent played the expected move, start the clock now
+    pub fn ponderhit(&mut self) {
+        self.start = Some(Instant::now());
+        self.iteration_start = self.start;
+    }
+
+    // time used on our clock
+    pub fn elapsed(&self) -> u128 {
+        millis_since(self.start)
+    }
+
+    // time since the search started, including pondering
+    pub fn search_time(&self) -> u128 {
+        millis_since(self.search_start)
+    }
+
+    // check if the search has to be aborted right away
+    pub fn hard_stop(&self) -> bool {
+        self.timeset && self.elapsed() >= self.hard_limit
+    }
+
+    // record the result of a finished iteration
+    pub fn update(&mut self, best_move: u32, score: i16) {
+        if best_move == self.best_move {
+            self.stability = (self.stability + 1).min(STABILITY_SCALE.len() - 1);
+        } else {
+            self.stability = 0;
+        }
+        self.score_drop = if self.best_move != 0 { self.score as i32 - score as i32 } else { 0 };
+        self.best_move = best_move;
+        self.score = score;
+
+        self.last_iteration = millis_since(self.iteration_start);
+        self.iteration_start = Some(Instant::now());
+    }
+
+    // check if another iteration is worth starting
+    pub fn stop_iteration(&self) -> bool {
+        if !self.timeset {
+            return false;
+        }
+        let elapsed = self.elapsed();
+
+        // think longer when the best move keeps changing or the score is dropping
+        let mut scale = if self.movetime != -1 { 1.0 } else { STABILITY_SCALE[self.stability] };
+        if self.movetime == -1 && self.score_drop > 0 {
+            scale *= 1.0 + (self.score_drop as f32 / 100.0).min(1.0);
+        }
+        let soft_limit = ((self.soft_limit as f32 * scale) as u128).min(self.hard_limit);
+        if elapsed >= soft_limit {
+            return true;
+        }
+
+        // don't start an iteration that won't finish before the hard limit
+        elapsed + self.last_iteration * BRANCHING_FACTOR >= self.hard_limit
+    }
+}
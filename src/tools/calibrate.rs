@@ -0,0 +1,133 @@
+use crate::search::*;
+use crate::tools::*;
+
+pub const CALIBRATE_USAGE: &str = "usage: calibrate [levels=<n,n,...> | elos=<n,n,...>] [anchor=<level>] [games=<n>] \
+[tc=<base>+<inc> | nodes=<n> | movetime=<ms>] [concurrency=<n>] [openings=<epd or pgn file>] [engine=<path>] [other match options]";
+
+// the strength a calibration match is played at
+#[derive(Clone, Copy, Debug)]
+enum Strength {
+    Level(u8),
+    Elo(i32),
+}
+
+impl Strength {
+    // the match options that set this strength for the first or second engine
+    fn options(&self, engine: usize) -> Vec<String> {
+        match self {
+            Strength::Level(level) => vec![format!("option{}.Skill Level={}", engine, level)],
+            Strength::Elo(elo) => vec![format!("option{}.UCI_LimitStrength=true", engine), format!("option{}.UCI_Elo={}", engine, elo)],
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Strength::Level(level) => format!("Skill Level {}", level),
+            Strength::Elo(elo) => format!("UCI_Elo {}", elo),
+        }
+    }
+
+    // the rating the engine should play at according to the calibration table
+    fn expected_elo(&self) -> i32 {
+        match self {
+            Strength::Level(level) => FULL_STRENGTH_ELO + SKILL_ELO.get(*level as usize).copied().unwrap_or(0),
+            Strength::Elo(elo) => *elo,
+        }
+    }
+}
+
+fn parse_list<T: std::str::FromStr>(key: &str, value: &str) -> Result<Vec<T>, String> {
+    value.split(',').map(|item| item.trim().parse::<T>().map_err(|_| format!("invalid value \"{}\" for {}", item, key))).collect()
+}
+
+// the "calibrate" command, plays each strength against the full strength engine (or a fixed level) and
+// estimates the rating it plays at, the results are what `SKILL_ELO` is filled with
+pub fn run_calibration(args: &[String]) {
+    let mut strengths: Vec<Strength> = [0, 5, 10, 15, 19].iter().map(|&level| Strength::Level(level)).collect();
+    let mut anchor: Option<u8> = None;
+    let mut match_args = vec!["games=100".to_string()];
+
+    for arg in args {
+        let (key, value) = match arg.split_once('=') {
+            Some(pair) => pair,
+            None => {
+                send!("expected key=value, got \"{}\"\n{}", arg, CALIBRATE_USAGE);
+                return;
+            }
+        };
+        let parsed = match key.to_lowercase().as_str() {
+            "levels" => parse_list::<u8>(key, value).map(|levels| strengths = levels.into_iter().map(Strength::Level).collect()),
+            "elos" => parse_list::<i32>(key, value).map(|elos| strengths = elos.into_iter().map(Strength::Elo).collect()),
+            "anchor" => parse_list::<u8>(key, value).map(|level| anchor = level.first().copied()),
+            "engine" => {
+                match_args.push(format!("engine1={}", value));
+                match_args.push(format!("engine2={}", value));
+                Ok(())
+            }
+            // everything else is a match option
+            _ => {
+                match_args.push(arg.clone());
+                Ok(())
+            }
+        };
+        if let Err(error) = parsed {
+            send!("{}\n{}", error, CALIBRATE_USAGE);
+            return;
+        }
+    }
+    if let Some(level) = strengths.iter().chain(anchor.map(Strength::Level).iter()).find_map(|strength| match strength {
+        Strength::Level(level) if *level > MAX_SKILL_LEVEL => Some(*level),
+        _ => None,
+    }) {
+        send!("invalid skill level {}, levels go from 0 to {}\n{}", level, MAX_SKILL_LEVEL, CALIBRATE_USAGE);
+        return;
+    }
+
+    // the engine everything is measured against, and the rating it plays at
+    let (anchor_name, anchor_elo, anchor_options) = match anchor {
+        Some(level) => (Strength::Level(level).name(), Strength::Level(level).expected_elo(), Strength::Level(level).options(2)),
+        None => ("full strength".to_string(), FULL_STRENGTH_ELO, Vec::new()),
+    };
+
+    let mut results = Vec::with_capacity(strengths.len());
+    for strength in strengths.iter() {
+        let mut args = match_args.clone();
+        args.push(format!("name1={}", strength.name()));
+        args.push(format!("name2={}", anchor_name));
+        args.extend(strength.options(1));
+        args.extend(anchor_options.iter().cloned());
+
+        let config = match MatchConfig::parse(&args) {
+            Ok(config) => config,
+            Err(error) => {
+                send!("{}\n{}", error, CALIBRATE_USAGE);
+                return;
+            }
+        };
+        match play_match(config) {
+            Some(score) if score.games() > 0 => results.push((*strength, score)),
+            _ => return,
+        }
+    }
+
+    send!("\nCalibration against {} ({} Elo):", anchor_name, anchor_elo);
+    send!("{:<16} {:>14} {:>8} {:>18} {:>8} {:>8}", "strength", "W - L - D", "score", "Elo difference", "rating", "table");
+    for (strength, score) in results.iter() {
+        let points = (score.wins as f64 + score.draws as f64 / 2.0) / score.games() as f64;
+        let record = format!("{} - {} - {}", score.wins, score.losses, score.draws);
+        // a clean sweep only bounds the difference, count one game as a draw to get the bound
+        let (difference, bound) = if score.wins + score.draws == 0 {
+            (MatchScore { wins: 0, losses: score.games() - 1, draws: 1 }.elo().0, "<")
+        } else if score.losses + score.draws == 0 {
+            (MatchScore { wins: score.games() - 1, losses: 0, draws: 1 }.elo().0, ">")
+        } else {
+            (score.elo().0, "")
+        };
+        let margin = if bound.is_empty() { format!(" +/- {:.0}", score.elo().1) } else { String::new() };
+        send!(
+            "{:<16} {:>14} {:>8.3} {:>18} {:>8} {:>8}",
+            strength.name(), record, points, format!("{}{:.0}{}", bound, difference, margin),
+            format!("{}{:.0}", bound, anchor_elo as f64 + difference), strength.expected_elo()
+        );
+    }
+}
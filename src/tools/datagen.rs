@@ -0,0 +1,615 @@
+use crate::board::position::*;
+use crate::r#move::*;
+use crate::tools::*;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// how long a fixed node search may take before the engine counts as stalled
+const STALL_TIMEOUT: Duration = Duration::from_secs(60);
+// a worker gives up after this many failed games in a row, something is wrong with the engine or the settings
+const MAX_FAILED_GAMES: usize = 100;
+// size of a position in the binary format
+pub const PACKED_SIZE: usize = 35;
+
+pub const DATAGEN_USAGE: &str = "usage: datagen [engine=<path>] [option.<name>=<value>] [nodes=<n>] [games=<n>] [threads=<n>] \
+[randomplies=<n>] [openings=<epd or pgn file>] [maxopening=<cp>] [output=<path without extension>] [format=text|binary|both] [seed=<n>]";
+
+// the result of a game from white's point of view
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WdlResult {
+    BlackWin,
+    Draw,
+    WhiteWin,
+}
+
+impl WdlResult {
+    fn from_pgn(result: &str) -> WdlResult {
+        match result {
+            "1-0" => WdlResult::WhiteWin,
+            "0-1" => WdlResult::BlackWin,
+            _ => WdlResult::Draw,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        ["0.0", "0.5", "1.0"][self as usize]
+    }
+}
+
+// a position with its search score and the game result, as stored in the binary format:
+//   occupancy  u64      squares with a piece on them, a8 = bit 0
+//   pieces     [u8;16]  4 bits per piece (0-11) in the order of the occupancy bits, the low nibble first
+//   side       u8
+//   castling   u8       the castling bits of `Position::castle`
+//   enpassant  u8       64 if there is no en passant square
+//   halfmove   u8
+//   fullmove   u16
+//   score      i16      centipawns from white's point of view
+//   result     u8       0 = black wins, 1 = draw, 2 = white wins
+//   best move  u16      `Move::to_compact`, use `Position::decode_move` to get the full move
+// all numbers are little endian
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PackedPosition {
+    pub occupancy: u64,
+    pub pieces: [u8; 16],
+    pub side: u8,
+    pub castling: u8,
+    pub enpassant: u8,
+    pub halfmove: u8,
+    pub fullmove: u16,
+    pub score: i16,
+    pub result: WdlResult,
+    pub best_move: u16,
+}
+
+impl PackedPosition {
+    pub fn new(position: &Position, score: i16, result: WdlResult, best_move: u32) -> PackedPosition {
+        let mut occupancy = 0u64;
+        let mut pieces = [0u8; 16];
+        let mut count = 0;
+        for square in 0..64 {
+            if let Some(piece) = (0..12).find(|&piece| position.bitboards[piece].get(square) != 0) {
+                occupancy |= 1 << square;
+                pieces[count / 2] |= (piece as u8) << (4 * (count % 2));
+                count += 1;
+            }
+        }
+        PackedPosition {
+            occupancy,
+            pieces,
+            side: position.side as u8,
+            castling: position.castle,
+            enpassant: position.enpassant as u8,
+            halfmove: position.halfmove.min(255) as u8,
+            fullmove: position.fullmove,
+            score,
+            result,
+            best_move: Move(best_move).to_compact(),
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; PACKED_SIZE] {
+        let mut bytes = [0u8; PACKED_SIZE];
+        bytes[0..8].copy_from_slice(&self.occupancy.to_le_bytes());
+        bytes[8..24].copy_from_slice(&self.pieces);
+        bytes[24] = self.side;
+        bytes[25] = self.castling;
+        bytes[26] = self.enpassant;
+        bytes[27] = self.halfmove;
+        bytes[28..30].copy_from_slice(&self.fullmove.to_le_bytes());
+        bytes[30..32].copy_from_slice(&self.score.to_le_bytes());
+        bytes[32] = self.result as u8;
+        bytes[33..35].copy_from_slice(&self.best_move.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; PACKED_SIZE]) -> Result<PackedPosition, String> {
+        let result = match bytes[32] {
+            0 => WdlResult::BlackWin,
+            1 => WdlResult::Draw,
+            2 => WdlResult::WhiteWin,
+            result => return Err(format!("invalid game result {}", result)),
+        };
+        let mut pieces = [0u8; 16];
+        pieces.copy_from_slice(&bytes[8..24]);
+        Ok(PackedPosition {
+            occupancy: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            pieces,
+            side: bytes[24],
+            castling: bytes[25],
+            enpassant: bytes[26],
+            halfmove: bytes[27],
+            fullmove: u16::from_le_bytes([bytes[28], bytes[29]]),
+            score: i16::from_le_bytes([bytes[30], bytes[31]]),
+            result,
+            best_move: u16::from_le_bytes([bytes[33], bytes[34]]),
+        })
+    }
+
+    pub fn to_fen(self) -> String {
+        let mut board = [None; 64];
+        let mut occupancy = self.occupancy;
+        let mut count = 0;
+        while occupancy != 0 {
+            let square = occupancy.trailing_zeros() as usize;
+            board[square] = Some((self.pieces[count / 2] >> (4 * (count % 2))) & 0xf);
+            occupancy &= occupancy - 1;
+            count += 1;
+        }
+
+        let mut fen = String::with_capacity(64);
+        for rank in 0..8 {
+            let mut empty = 0;
+            for file in 0..8 {
+                match board[rank * 8 + file] {
+                    Some(piece) => {
+                        if empty != 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push_str(ASCII_PIECES[piece as usize % 12]);
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty != 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank != 7 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(if self.side == 0 { " w " } else { " b " });
+        if self.castling == 0 {
+            fen.push('-');
+        }
+        for (right, char) in [(Castling::WK, 'K'), (Castling::WQ, 'Q'), (Castling::BK, 'k'), (Castling::BQ, 'q')] {
+            if self.castling & right as u8 != 0 {
+                fen.push(char);
+            }
+        }
+        fen.push(' ');
+        fen.push_str(SQUARE_COORDS.get(self.enpassant as usize).copied().filter(|_| self.enpassant < 64).unwrap_or("-"));
+        fen.push_str(&format!(" {} {}", self.halfmove, self.fullmove));
+        fen
+    }
+
+    // the plain text format, "<fen> | <score> | <result>"
+    pub fn to_text(self) -> String {
+        format!("{} | {} | {}", self.to_fen(), self.score, self.result.as_str())
+    }
+}
+
+// reads the positions of a binary data file one by one
+pub struct DataReader {
+    reader: BufReader<File>,
+}
+
+impl DataReader {
+    pub fn open(path: &str) -> Result<DataReader, String> {
+        let file = File::open(path).map_err(|error| format!("failed to open {}: {}", path, error))?;
+        Ok(DataReader { reader: BufReader::new(file) })
+    }
+}
+
+impl Iterator for DataReader {
+    type Item = Result<PackedPosition, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut bytes = [0u8; PACKED_SIZE];
+        match self.reader.read_exact(&mut bytes) {
+            Ok(()) => Some(PackedPosition::from_bytes(&bytes)),
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => None,
+            Err(error) => Some(Err(error.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DataFormat {
+    Text,
+    Binary,
+    Both,
+}
+
+pub struct DatagenConfig {
+    pub engine: EngineConfig,
+    pub nodes: u64,
+    pub games: usize,
+    pub threads: usize,
+    // random moves played from the opening before the engine takes over
+    pub random_plies: usize,
+    pub openings: Vec<Opening>,
+    // openings where the first search is further from 0 than this are thrown away
+    pub max_opening_score: i32,
+    pub output: String,
+    pub format: DataFormat,
+    pub seed: u64,
+}
+
+impl DatagenConfig {
+    pub fn parse(args: &[String]) -> Result<DatagenConfig, String> {
+        let own_binary = std::env::current_exe().map_err(|error| format!("failed to find the engine binary: {}", error))?;
+        let mut config = DatagenConfig {
+            engine: EngineConfig { name: String::new(), command: own_binary.to_string_lossy().to_string(), options: Vec::new() },
+            nodes: 5000,
+            games: 100,
+            threads: 1,
+            random_plies: 8,
+            openings: Vec::new(),
+            max_opening_score: 400,
+            output: "data".to_string(),
+            format: DataFormat::Both,
+            seed: SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos() as u64).unwrap_or(0),
+        };
+
+        for arg in args {
+            let (key, value) = arg.split_once('=').ok_or(format!("expected key=value, got \"{}\"", arg))?;
+            if let Some(name) = key.strip_prefix("option.") {
+                config.engine.options.push((name.to_string(), value.to_string()));
+                continue;
+            }
+            let parse = |value: &str| value.parse::<u64>().map_err(|_| format!("invalid value \"{}\" for {}", value, key));
+            match key.to_lowercase().as_str() {
+                "engine" => config.engine.command = value.to_string(),
+                "nodes" => config.nodes = parse(value)?.max(1),
+                "games" => config.games = parse(value)? as usize,
+                "threads" => config.threads = parse(value)?.max(1) as usize,
+                "randomplies" => config.random_plies = parse(value)? as usize,
+                "openings" => config.openings = load_openings(value)?,
+                "maxopening" => config.max_opening_score = parse(value)? as i32,
+                "output" => config.output = value.to_string(),
+                "format" => config.format = match value.to_lowercase().as_str() {
+                    "text" => DataFormat::Text,
+                    "binary" => DataFormat::Binary,
+                    "both" => DataFormat::Both,
+                    _ => return Err(format!("unknown format \"{}\"", value)),
+                },
+                "seed" => config.seed = parse(value)?,
+                _ => return Err(format!("unknown datagen setting \"{}\"", key)),
+            }
+        }
+        if config.openings.is_empty() {
+            config.openings.push(Opening { fen: START_FEN.to_string(), moves: Vec::new() });
+        }
+        Ok(config)
+    }
+}
+
+// where the finished games go, shared by the threads
+struct DataWriter {
+    text: Option<BufWriter<File>>,
+    binary: Option<BufWriter<File>>,
+    games: usize,
+    positions: usize,
+}
+
+impl DataWriter {
+    fn write(&mut self, positions: &[PackedPosition]) -> std::io::Result<()> {
+        for position in positions {
+            if let Some(text) = self.text.as_mut() {
+                writeln!(text, "{}", position.to_text())?;
+            }
+            if let Some(binary) = self.binary.as_mut() {
+                binary.write_all(&position.to_bytes())?;
+            }
+        }
+        self.games += 1;
+        self.positions += positions.len();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if let Some(text) = self.text.as_mut() {
+            text.flush()?;
+        }
+        if let Some(binary) = self.binary.as_mut() {
+            binary.flush()?;
+        }
+        Ok(())
+    }
+}
+
+// a random opening, None if the random moves ended the game
+fn random_opening(config: &DatagenConfig, rng: &mut ChaCha8Rng) -> Option<(Position, String, String)> {
+    let opening = &config.openings[rng.gen_range(0..config.openings.len())];
+    // `load_openings` already dropped the positions that can't be parsed or played
+    let mut position = Position::from_fen_lenient(&opening.fen).expect("openings are validated when they are loaded");
+    let mut uci_moves = String::new();
+    for &move_ in opening.moves.iter() {
+        position.make(move_);
+        uci_moves.push_str(&format!(" {}", Move(move_)));
+    }
+    for _ in 0..config.random_plies {
+        let mut move_list = MoveList::new();
+        position.generate_moves(GenType::All, &mut move_list);
+        if move_list.as_slice().is_empty() {
+            return None;
+        }
+        let move_ = move_list.as_slice()[rng.gen_range(0..move_list.as_slice().len())].0;
+        position.make(move_);
+        uci_moves.push_str(&format!(" {}", Move(move_)));
+    }
+    if game_over(&position).is_some() {
+        return None;
+    }
+    Some((position, opening.fen.clone(), uci_moves))
+}
+
+// play a game from a random opening, returns the positions worth keeping labelled with the result
+// or why the game was thrown away
+fn play_game(config: &DatagenConfig, engine: &mut Engine, rng: &mut ChaCha8Rng) -> Result<Vec<PackedPosition>, String> {
+    let (mut position, fen, mut uci_moves) = random_opening(config, rng).ok_or("the game was over after the opening moves")?;
+    engine.new_game()?;
+
+    // the result is filled in once the game is over
+    let mut samples = Vec::new();
+    let go = format!("go nodes {}", config.nodes);
+    // plies in a row where one side was clearly winning (positive for white) or the game was dead even
+    let mut winning_plies = 0i32;
+    let mut drawn_plies = 0;
+    let mut first_search = true;
+
+    let result = loop {
+        if let Some(result) = game_over(&position) {
+            break WdlResult::from_pgn(result.result);
+        }
+
+        let search = engine.go(&format!("position fen {} moves{}", fen, uci_moves), &go, STALL_TIMEOUT)
+            .ok_or("the engine stalled or exited")?;
+        let move_ = find_move(&position, &search.best_move);
+        if move_ == 0 {
+            return Err(format!("the engine played the illegal move \"{}\"", search.best_move));
+        }
+        let score = search.score.unwrap_or(0);
+        if first_search && score.abs() > config.max_opening_score {
+            return Err(format!("the opening score {} is above maxopening", score));
+        }
+        first_search = false;
+
+        // only quiet positions with a real score are useful for training
+        if position.checkers() == 0 && capture(move_) == 0 && search.score.is_some() && !search.is_mate() {
+            let white_score = if position.side == Side::WHITE { score } else { -score };
+            samples.push(PackedPosition::new(&position, white_score as i16, WdlResult::Draw, move_));
+        }
+
+        // adjudicate clearly decided games, the result is all that matters
+        let white_score = if position.side == Side::WHITE { score } else { -score };
+        winning_plies = match white_score {
+            score if score >= 2000 => winning_plies.max(0) + 1,
+            score if score <= -2000 => winning_plies.min(0) - 1,
+            _ => 0,
+        };
+        if winning_plies.abs() >= 6 {
+            break if winning_plies > 0 { WdlResult::WhiteWin } else { WdlResult::BlackWin };
+        }
+        drawn_plies = if position.fullmove >= 40 && white_score.abs() <= 10 { drawn_plies + 1 } else { 0 };
+        if drawn_plies >= 12 {
+            break WdlResult::Draw;
+        }
+
+        position.make(move_);
+        uci_moves.push_str(&format!(" {}", Move(move_)));
+    };
+
+    for sample in samples.iter_mut() {
+        sample.result = result;
+    }
+    Ok(samples)
+}
+
+fn run_worker(config: &DatagenConfig, thread: usize, next_game: &AtomicUsize, stop: &AtomicBool, writer: &Mutex<DataWriter>) -> Result<(), String> {
+    let mut rng = ChaCha8Rng::seed_from_u64(config.seed.wrapping_add(thread as u64));
+    let mut engine = None;
+    let mut failed_games = 0;
+    // a failed game is played again by the worker that reserved it, giving the reservation back could lose
+    // the game when the other workers already saw that all games were taken
+    while !stop.load(Ordering::Relaxed) && next_game.fetch_add(1, Ordering::Relaxed) < config.games {
+        let positions = loop {
+            if stop.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            if engine.is_none() {
+                engine = Some(Engine::start(&config.engine)?);
+            }
+
+            match play_game(config, engine.as_mut().unwrap(), &mut rng) {
+                Ok(positions) => break positions,
+                Err(error) => {
+                    failed_games += 1;
+                    if failed_games >= MAX_FAILED_GAMES {
+                        return Err(format!("{} games in a row failed on thread {}, last error: {}", failed_games, thread, error));
+                    }
+                    // restart the engine in case it crashed
+                    if engine.as_mut().unwrap().ready().is_err() {
+                        engine = None;
+                    }
+                }
+            }
+        };
+        failed_games = 0;
+
+        let mut writer = writer.lock().unwrap();
+        writer.write(&positions).map_err(|error| format!("failed to write the data: {}", error))?;
+    }
+    Ok(())
+}
+
+// the "datagen" command, fixed node self-play that writes labelled positions for training and tuning
+pub fn run_datagen(args: &[String]) {
+    let config = match DatagenConfig::parse(args) {
+        Ok(config) => config,
+        Err(error) => {
+            send!("{}\n{}", error, DATAGEN_USAGE);
+            return;
+        }
+    };
+
+    let create = |extension: &str| -> Result<BufWriter<File>, String> {
+        let path = format!("{}.{}", config.output, extension);
+        File::create(&path).map(BufWriter::new).map_err(|error| format!("failed to create {}: {}", path, error))
+    };
+    let (text, binary) = match config.format {
+        DataFormat::Text => (create("txt").map(Some), Ok(None)),
+        DataFormat::Binary => (Ok(None), create("bin").map(Some)),
+        DataFormat::Both => (create("txt").map(Some), create("bin").map(Some)),
+    };
+    let writer = match (text, binary) {
+        (Ok(text), Ok(binary)) => DataWriter { text, binary, games: 0, positions: 0 },
+        (Err(error), _) | (_, Err(error)) => {
+            send!("{}", error);
+            return;
+        }
+    };
+
+    send!("Generating {} games with {} nodes per move on {} thread(s), seed {}", config.games, config.nodes, config.threads, config.seed);
+    let start = Instant::now();
+    let config = Arc::new(config);
+    let next_game = Arc::new(AtomicUsize::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let writer = Arc::new(Mutex::new(writer));
+
+    let workers: Vec<_> = (0..config.threads).map(|thread| {
+        let (config, next_game, stop, writer) = (config.clone(), next_game.clone(), stop.clone(), writer.clone());
+        thread::spawn(move || {
+            let result = run_worker(&config, thread, &next_game, &stop, &writer);
+            // the other workers stop too when one of them fails
+            if result.is_err() {
+                stop.store(true, Ordering::Relaxed);
+            }
+            result
+        })
+    }).collect();
+
+    // report the progress every few seconds
+    let mut last_report = Instant::now();
+    while workers.iter().any(|worker| !worker.is_finished()) {
+        thread::sleep(Duration::from_millis(100));
+        if last_report.elapsed() >= Duration::from_secs(10) {
+            last_report = Instant::now();
+            let writer = writer.lock().unwrap();
+            let seconds = start.elapsed().as_secs_f64();
+            send!("{} games, {} positions, {:.0} positions/s", writer.games, writer.positions, writer.positions as f64 / seconds);
+        }
+    }
+    let mut failed = false;
+    for worker in workers {
+        if let Ok(Err(error)) = worker.join() {
+            send!("{}", error);
+            failed = true;
+        }
+    }
+
+    let mut writer = writer.lock().unwrap();
+    if let Err(error) = writer.flush() {
+        send!("failed to write the data: {}", error);
+    }
+    send!("{}: {} games, {} positions in {:.1} s", if failed { "Aborted" } else { "Finished" }, writer.games, writer.positions, start.elapsed().as_secs_f64());
+}
+
+// the "dataconvert" command, turns a binary data file into the text format
+pub fn convert_data(args: &[String]) {
+    let (input, output) = match args {
+        [input, output] => (input, output),
+        _ => {
+            send!("usage: dataconvert <binary file> <text file>");
+            return;
+        }
+    };
+    let reader = match DataReader::open(input) {
+        Ok(reader) => reader,
+        Err(error) => {
+            send!("{}", error);
+            return;
+        }
+    };
+    let mut writer = match File::create(output) {
+        Ok(file) => BufWriter::new(file),
+        Err(error) => {
+            send!("failed to create {}: {}", output, error);
+            return;
+        }
+    };
+
+    let mut count = 0;
+    for position in reader {
+        let written = position.and_then(|position| writeln!(writer, "{}", position.to_text()).map_err(|error| error.to_string()));
+        if let Err(error) = written {
+            send!("failed after {} positions: {}", count, error);
+            return;
+        }
+        count += 1;
+    }
+    if let Err(error) = writer.flush() {
+        send!("failed to write {}: {}", output, error);
+        return;
+    }
+    send!("Converted {} positions", count);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::attacks::init_tests;
+
+    // castling rights, en passant and both sides to move, each with a different result
+    const SAMPLES: [(&str, i16, WdlResult); 4] = [
+        ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 25, WdlResult::WhiteWin),
+        ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", -40, WdlResult::Draw),
+        ("r3k3/8/8/8/4p3/8/3P4/4K2R b Kq - 17 42", -312, WdlResult::BlackWin),
+        ("8/8/4k3/8/2pP4/8/8/4K3 b - d3 0 60", 0, WdlResult::Draw),
+    ];
+
+    fn packed_samples() -> Vec<(PackedPosition, Position, u32)> {
+        SAMPLES.iter().map(|&(fen, score, result)| {
+            let position = Position::from_fen(fen).unwrap();
+            let mut move_list = MoveList::new();
+            position.generate_moves(GenType::All, &mut move_list);
+            let best_move = move_list.as_slice().last().unwrap().0;
+            (PackedPosition::new(&position, score, result, best_move), position, best_move)
+        }).collect()
+    }
+
+    #[test]
+    fn packed_position_round_trip() {
+        init_tests();
+        for ((packed, position, best_move), (fen, score, result)) in packed_samples().into_iter().zip(SAMPLES) {
+            assert_eq!(packed.to_fen(), fen);
+            assert_eq!(packed.to_text(), format!("{} | {} | {}", fen, score, result.as_str()));
+            assert_eq!(position.decode_move(packed.best_move).0, best_move, "{}", fen);
+
+            let bytes = packed.to_bytes();
+            assert_eq!(PackedPosition::from_bytes(&bytes), Ok(packed));
+            let mut corrupt = bytes;
+            corrupt[32] = 3;
+            assert_eq!(PackedPosition::from_bytes(&corrupt), Err("invalid game result 3".to_string()));
+        }
+    }
+
+    #[test]
+    fn data_reader() {
+        init_tests();
+        let samples = packed_samples();
+        let path = std::env::temp_dir().join(format!("hydrochess-datagen-test-{}.bin", std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        for (packed, _, _) in samples.iter() {
+            file.write_all(&packed.to_bytes()).unwrap();
+        }
+        drop(file);
+
+        let read: Result<Vec<PackedPosition>, String> = DataReader::open(path.to_str().unwrap()).unwrap().collect();
+        std::fs::remove_file(&path).unwrap();
+        let fens: Vec<String> = read.unwrap().into_iter().map(|packed| packed.to_fen()).collect();
+        assert_eq!(fens, SAMPLES.map(|(fen, _, _)| fen));
+        assert!(DataReader::open("does/not/exist.bin").is_err());
+    }
+}
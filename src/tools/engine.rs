@@ -0,0 +1,157 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// how long an engine gets to answer "uci" and "isready"
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+// reported mate scores are at least this far from 0
+pub const MATE_THRESHOLD: i32 = 30000 - 2 * 500;
+
+// an engine to play with, the binary and the UCI options to set
+#[derive(Clone, Debug)]
+pub struct EngineConfig {
+    pub name: String,
+    pub command: String,
+    pub options: Vec<(String, String)>,
+}
+
+// what the engine answered to "go"
+pub struct SearchResult {
+    pub best_move: String,
+    // the last reported score in centipawns from the engine's point of view, mates are +-(30000 - plies)
+    pub score: Option<i32>,
+    pub time: Duration,
+}
+
+impl SearchResult {
+    pub fn is_mate(&self) -> bool {
+        self.score.map(|score| score.abs() >= MATE_THRESHOLD).unwrap_or(false)
+    }
+}
+
+// a UCI engine running as a child process
+pub struct Engine {
+    pub name: String,
+    child: Child,
+    stdin: ChildStdin,
+    // lines the engine printed, read by a separate thread so we can wait with a timeout
+    lines: Receiver<String>,
+}
+
+impl Engine {
+    pub fn start(config: &EngineConfig) -> Result<Engine, String> {
+        let mut child = Command::new(&config.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|error| format!("failed to start {}: {}", config.command, error))?;
+
+        let stdin = child.stdin.take().ok_or("failed to open engine stdin")?;
+        let stdout = child.stdout.take().ok_or("failed to open engine stdout")?;
+        let (sender, lines) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => if sender.send(line).is_err() { break; },
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let mut engine = Engine { name: config.name.clone(), child, stdin, lines };
+        engine.send("uci");
+        let lines = engine.wait_for("uciok", STARTUP_TIMEOUT).ok_or(format!("{} didn't answer \"uci\"", config.name))?;
+        // use the engine's own name unless we were given one
+        if engine.name.is_empty() {
+            engine.name = lines.iter().find_map(|line| line.strip_prefix("id name ")).unwrap_or(&config.command).to_string();
+        }
+        for (name, value) in config.options.iter() {
+            engine.send(&format!("setoption name {} value {}", name, value));
+        }
+        engine.ready()?;
+        Ok(engine)
+    }
+
+    pub fn send(&mut self, command: &str) {
+        // a dead engine is noticed when it doesn't answer
+        let _ = writeln!(self.stdin, "{}", command);
+        let _ = self.stdin.flush();
+    }
+
+    // read lines until one starts with `token`, None on timeout or if the engine quit
+    fn wait_for(&mut self, token: &str, timeout: Duration) -> Option<Vec<String>> {
+        let deadline = Instant::now() + timeout;
+        let mut lines = Vec::new();
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.lines.recv_timeout(remaining) {
+                Ok(line) => {
+                    let done = line.starts_with(token);
+                    lines.push(line);
+                    if done {
+                        return Some(lines);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+
+    pub fn ready(&mut self) -> Result<(), String> {
+        self.send("isready");
+        self.wait_for("readyok", STARTUP_TIMEOUT).map(|_| ()).ok_or(format!("{} didn't answer \"isready\"", self.name))
+    }
+
+    pub fn new_game(&mut self) -> Result<(), String> {
+        self.send("ucinewgame");
+        self.ready()
+    }
+
+    // search the position given as a "position ..." command, None if the engine didn't answer in time
+    pub fn go(&mut self, position: &str, go: &str, timeout: Duration) -> Option<SearchResult> {
+        self.send(position);
+        self.send(go);
+        let start = Instant::now();
+        let lines = self.wait_for("bestmove", timeout)?;
+        let time = start.elapsed();
+
+        let best_move = lines.last()?.split_whitespace().nth(1)?.to_string();
+        let mut score = None;
+        for line in lines.iter().filter(|line| line.starts_with("info")) {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            // scores of failed aspiration searches are only bounds
+            if tokens.contains(&"lowerbound") || tokens.contains(&"upperbound") {
+                continue;
+            }
+            if let Some(index) = tokens.iter().position(|&token| token == "score") {
+                let value = tokens.get(index + 2).and_then(|value| value.parse::<i32>().ok());
+                score = match (tokens.get(index + 1), value) {
+                    (Some(&"cp"), Some(value)) => Some(value),
+                    (Some(&"mate"), Some(value)) if value > 0 => Some(30000 - value * 2),
+                    (Some(&"mate"), Some(value)) => Some(-30000 - value * 2),
+                    _ => score,
+                };
+            }
+        }
+        Some(SearchResult { best_move, score, time })
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.send("quit");
+        // give it a moment to quit on its own
+        for _ in 0..50 {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
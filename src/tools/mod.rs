@@ -0,0 +1,17 @@
+pub mod sprt;
+pub use sprt::*;
+
+pub mod engine;
+pub use engine::*;
+
+pub mod pgn;
+pub use pgn::*;
+
+pub mod runner;
+pub use runner::*;
+
+pub mod datagen;
+pub use datagen::*;
+
+pub mod calibrate;
+pub use calibrate::*;
@@ -0,0 +1,382 @@
+use crate::board::position::*;
+use crate::r#move::*;
+
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const PIECE_LETTERS: [char; 6] = ['P', 'N', 'B', 'R', 'Q', 'K'];
+
+// a position to start games from, the moves are played out as part of the game
+#[derive(Clone, Debug)]
+pub struct Opening {
+    pub fen: String,
+    pub moves: Vec<u32>,
+}
+
+// a finished game
+pub struct GameRecord {
+    pub event: String,
+    pub round: usize,
+    pub white: String,
+    pub black: String,
+    pub fen: String,
+    pub moves: Vec<u32>,
+    pub result: &'static str,
+    pub termination: String,
+    pub time_control: String,
+}
+
+fn file_char(square: usize) -> char {
+    (b'a' + (square % 8) as u8) as char
+}
+
+fn rank_char(square: usize) -> char {
+    (b'8' - (square / 8) as u8) as char
+}
+
+impl Position {
+    // standard algebraic notation of a legal move
+    pub fn to_san(&self, move_: u32) -> String {
+        let mut san = String::new();
+        let piece = get_piece(move_) as usize % 6;
+        let source_square = source(move_) as usize;
+        let target_square = target(move_) as usize;
+
+        if castling(move_) != 0 {
+            san.push_str(if target_square % 8 == 6 { "O-O" } else { "O-O-O" });
+        } else {
+            if piece == Piece::WhitePawn as usize {
+                // pawn captures start with the file they come from
+                if capture(move_) != 0 {
+                    san.push(file_char(source_square));
+                }
+            } else {
+                san.push(PIECE_LETTERS[piece]);
+
+                // tell the move apart from moves of the same kind of piece to the same square
+                let mut move_list = MoveList::new();
+                self.generate_moves(GenType::All, &mut move_list);
+                let others: Vec<usize> = move_list.as_slice().iter()
+                    .filter(|other| other.0 != move_ && other.piece() == get_piece(move_) && other.target() == target_square as u8)
+                    .map(|other| other.source() as usize)
+                    .collect();
+                if !others.is_empty() {
+                    if others.iter().all(|&other| other % 8 != source_square % 8) {
+                        san.push(file_char(source_square));
+                    } else if others.iter().all(|&other| other / 8 != source_square / 8) {
+                        san.push(rank_char(source_square));
+                    } else {
+                        san.push(file_char(source_square));
+                        san.push(rank_char(source_square));
+                    }
+                }
+            }
+
+            if capture(move_) != 0 {
+                san.push('x');
+            }
+            san.push_str(SQUARE_COORDS[target_square]);
+            if promoted(move_) != 0 {
+                san.push('=');
+                san.push(PIECE_LETTERS[promoted(move_) as usize % 6]);
+            }
+        }
+
+        // check or mate
+        let mut position = self.clone();
+        position.make(move_);
+        if position.checkers() != 0 {
+            san.push(if position.count_legal_moves() == 0 { '#' } else { '+' });
+        }
+        san
+    }
+
+    // find the legal move for a move in standard algebraic notation, 0 if there is none
+    pub fn parse_san(&self, san: &str) -> u32 {
+        let strip = |san: &str| san.trim_end_matches(|char| "+#!?".contains(char)).replace("0-0", "O-O");
+        let san = strip(san);
+
+        let mut move_list = MoveList::new();
+        self.generate_moves(GenType::All, &mut move_list);
+        for move_ in move_list.as_slice() {
+            if strip(&self.to_san(move_.0)) == san {
+                return move_.0;
+            }
+        }
+        0
+    }
+}
+
+// today's date as used in PGN tags
+fn pgn_date() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs() / 86400).unwrap_or(0) as i64;
+    // days since 1970-01-01 to a civil date
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+impl GameRecord {
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        pgn.push_str(&format!("[Event \"{}\"]\n", self.event));
+        pgn.push_str("[Site \"?\"]\n");
+        pgn.push_str(&format!("[Date \"{}\"]\n", pgn_date()));
+        pgn.push_str(&format!("[Round \"{}\"]\n", self.round));
+        pgn.push_str(&format!("[White \"{}\"]\n", self.white));
+        pgn.push_str(&format!("[Black \"{}\"]\n", self.black));
+        pgn.push_str(&format!("[Result \"{}\"]\n", self.result));
+        if self.fen != START_FEN {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", self.fen));
+        }
+        pgn.push_str(&format!("[TimeControl \"{}\"]\n", self.time_control));
+        pgn.push_str(&format!("[Termination \"{}\"]\n", self.termination));
+        pgn.push_str(&format!("[PlyCount \"{}\"]\n\n", self.moves.len()));
+
+        // movetext, wrapped at 80 columns
+        let mut position = Position::from_fen_lenient(&self.fen).unwrap_or_else(|_| Position::new());
+        let mut tokens = Vec::with_capacity(self.moves.len() * 3 / 2 + 1);
+        for (index, &move_) in self.moves.iter().enumerate() {
+            if position.side == Side::WHITE {
+                tokens.push(format!("{}.", position.fullmove));
+            } else if index == 0 {
+                tokens.push(format!("{}...", position.fullmove));
+            }
+            tokens.push(position.to_san(move_));
+            position.make(move_);
+        }
+        tokens.push(self.result.to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + token.len() + 1 > 80 {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push_str("\n\n");
+        pgn
+    }
+}
+
+// read the openings from an EPD file (one position per line) or a PGN file (the moves of every game)
+// positions and games that can't be played are skipped with a message
+pub fn load_openings(path: &str) -> Result<Vec<Opening>, String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("failed to read {}: {}", path, error))?;
+    let (openings, skipped) = if path.to_lowercase().ends_with(".pgn") { parse_pgn_openings(&text) } else { parse_epd_openings(&text) };
+    for reason in skipped.iter() {
+        send!("skipping {} of {}", reason, path);
+    }
+    if openings.is_empty() {
+        return Err(format!("no openings found in {}", path));
+    }
+    Ok(openings)
+}
+
+// parse and check a position given in an EPD line or a FEN tag
+fn parse_opening_fen(fen: &str) -> Result<Position, String> {
+    Position::from_fen_lenient(fen).and_then(|position| position.validate().map(|_| position)).map_err(|error| error.to_string())
+}
+
+// returns the openings and why the others were skipped
+fn parse_epd_openings(text: &str) -> (Vec<Opening>, Vec<String>) {
+    let mut openings = Vec::new();
+    let mut skipped = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        // only the position matters, the opcodes are ignored
+        let fields: Vec<&str> = line.split_whitespace().take(4).collect();
+        if fields.is_empty() {
+            continue;
+        }
+        match parse_opening_fen(&fields.join(" ")) {
+            Ok(position) => openings.push(Opening { fen: position.to_fen(), moves: Vec::new() }),
+            Err(error) => skipped.push(format!("line {} ({})", number + 1, error)),
+        }
+    }
+    (openings, skipped)
+}
+
+// a game of a PGN file while it's read
+struct PgnGame {
+    fen: String,
+    fen_tag: bool,
+    position: Position,
+    moves: Vec<u32>,
+    // the first problem found in the game, the rest of it is ignored
+    error: Option<String>,
+}
+
+impl PgnGame {
+    fn new() -> PgnGame {
+        PgnGame { fen: START_FEN.to_string(), fen_tag: false, position: Position::new(), moves: Vec::new(), error: None }
+    }
+
+    fn fail(&mut self, error: String) {
+        self.error = self.error.take().or(Some(error));
+    }
+
+    // games without moves are kept when they start from their own position
+    fn finish(self, number: usize, openings: &mut Vec<Opening>, skipped: &mut Vec<String>) {
+        if let Some(error) = self.error {
+            skipped.push(format!("game {} ({})", number, error));
+        } else if self.fen_tag || !self.moves.is_empty() {
+            openings.push(Opening { fen: self.fen, moves: self.moves });
+        }
+    }
+}
+
+// returns the openings and why the others were skipped
+fn parse_pgn_openings(text: &str) -> (Vec<Opening>, Vec<String>) {
+    let mut openings = Vec::new();
+    let mut skipped = Vec::new();
+    let mut games = 1;
+    let mut game = PgnGame::new();
+    // a tag after the movetext (or the empty line that ends the tags) starts a new game
+    let mut in_tags = false;
+    let mut tags_seen = false;
+    // comments and variations are skipped
+    let mut comment = false;
+    let mut variation_depth = 0;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if !comment && variation_depth == 0 && line.starts_with('[') {
+            if !in_tags && tags_seen {
+                std::mem::replace(&mut game, PgnGame::new()).finish(games, &mut openings, &mut skipped);
+                games += 1;
+            }
+            in_tags = true;
+            tags_seen = true;
+
+            if let Some(tag) = line.strip_prefix("[FEN \"") {
+                game.fen_tag = true;
+                match parse_opening_fen(tag.trim_end_matches("\"]")) {
+                    Ok(position) => {
+                        game.fen = position.to_fen();
+                        game.position = position;
+                    }
+                    Err(error) => game.fail(format!("invalid FEN tag: {}", error)),
+                }
+            }
+            continue;
+        }
+        in_tags = false;
+        // a game without tags ends at the next tag too
+        tags_seen |= !line.is_empty();
+        if line.starts_with('%') {
+            continue;
+        }
+
+        let mut token = String::new();
+        // a trailing space so the last token of the line is handled too
+        for char in line.chars().chain(std::iter::once(' ')) {
+            if comment {
+                comment = char != '}';
+                continue;
+            }
+            if !(char.is_whitespace() || "{}();.".contains(char)) {
+                token.push(char);
+                continue;
+            }
+
+            // the token ends here, moves inside variations are skipped
+            if variation_depth == 0 && !token.is_empty() && game.error.is_none() {
+                if let Err(error) = play_pgn_token(&mut game.position, &mut game.moves, &token) {
+                    game.fail(error);
+                }
+            }
+            token.clear();
+            match char {
+                '{' => comment = true,
+                '(' => variation_depth += 1,
+                ')' if variation_depth == 0 => game.fail("unmatched \")\"".to_string()),
+                ')' => variation_depth -= 1,
+                // the rest of the line is a comment
+                ';' => break,
+                _ => (),
+            }
+        }
+    }
+    if comment || variation_depth > 0 {
+        game.fail("unterminated comment or variation".to_string());
+    }
+    game.finish(games, &mut openings, &mut skipped);
+    (openings, skipped)
+}
+
+// play a move of the movetext, move numbers, NAGs and results are skipped
+fn play_pgn_token(position: &mut Position, moves: &mut Vec<u32>, token: &str) -> Result<(), String> {
+    let is_result = ["1-0", "0-1", "1/2-1/2", "*"].contains(&token);
+    let is_annotation = token.starts_with('$') || token.chars().all(|char| char.is_ascii_digit());
+    if is_result || is_annotation {
+        return Ok(());
+    }
+    let move_ = position.parse_san(token);
+    if move_ == 0 {
+        return Err(format!("illegal move \"{}\" in {}", token, position.to_fen()));
+    }
+    position.make(move_);
+    moves.push(move_);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::attacks::init_tests;
+
+    #[test]
+    fn pgn_openings() {
+        init_tests();
+        let pgn = "[Event \"a\"]\n[FEN \"4k3/8/8/8/8/8/8/4K2R w K - 0 1\"]\n\n*\n\n\
+            [Event \"b\"]\n\n1. e4 (1. d4 d5) e5 {a comment} 2. Nf3 *\n\n\
+            [Event \"c\"]\n[FEN \"4k3/8/8/8/8/8/8/4K2R w K -\"]\n[Result \"*\"]\n\n\
+            [Event \"d\"]\n1. d4 d5 *\n";
+        let (openings, skipped) = parse_pgn_openings(pgn);
+        let summary: Vec<(&str, usize)> = openings.iter().map(|opening| (opening.fen.as_str(), opening.moves.len())).collect();
+        assert_eq!(summary, [
+            ("4k3/8/8/8/8/8/8/4K2R w K - 0 1", 0),
+            (START_FEN, 3),
+            ("4k3/8/8/8/8/8/8/4K2R w K - 0 1", 0),
+            (START_FEN, 2),
+        ]);
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn invalid_openings_are_skipped() {
+        init_tests();
+        let pgn = "[Event \"a\"]\n\n1. e4 ) e5 *\n\n\
+            [Event \"b\"]\n[FEN \"4k3/8/8/8/8/8/8/4K3 w K - 0 1\"]\n\n*\n\n\
+            [Event \"c\"]\n\n1. e4 e4 *\n\n\
+            [Event \"d\"]\n\n1. e4 *\n";
+        let (openings, skipped) = parse_pgn_openings(pgn);
+        assert_eq!(openings.len(), 1);
+        assert_eq!(skipped, [
+            "game 1 (unmatched \")\")",
+            "game 2 (invalid FEN tag: castling right \"K\" without the king and rook in place)",
+            "game 3 (illegal move \"e4\" in rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1)",
+        ]);
+
+        let (openings, skipped) = parse_epd_openings("4k3/8/8/8/8/8/8/4K3 w - -\n4k3/8/8/8/8/8/8/4K3 w - e6 bm Kd2;\n");
+        assert_eq!(openings.len(), 1);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("line 2 "));
+    }
+}
@@ -0,0 +1,529 @@
+use crate::board::position::*;
+use crate::r#move::*;
+use crate::tools::*;
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// how long a search with a fixed depth or node count may take before the engine counts as stalled
+const STALL_TIMEOUT: Duration = Duration::from_secs(60);
+// extra time to wait for "bestmove" after the clock ran out, so a late move is a time loss and not a stall
+const CLOCK_GRACE: Duration = Duration::from_secs(1);
+
+const COLOUR_NAMES: [&str; 2] = ["White", "Black"];
+
+pub const MATCH_USAGE: &str = "usage: match [engine1=<path>] [engine2=<path>] [name1=<name>] [name2=<name>] \
+[option1.<name>=<value>] [option2.<name>=<value>] [tc=<base>+<inc> | nodes=<n> | depth=<n> | movetime=<ms>] \
+[games=<n>] [concurrency=<n>] [openings=<epd or pgn file>] [pgnout=<file>] [sprt=<elo0>,<elo1>] [alpha=<a>] [beta=<b>] \
+[resign=<moves>,<score>] [draw=<movenumber>,<moves>,<score>] [maxmoves=<n>]";
+
+// how long the engines get for each move
+#[derive(Clone, Copy, Debug)]
+pub enum MatchLimit {
+    // base time and increment in milliseconds
+    Clock(u64, u64),
+    Nodes(u64),
+    Depth(u32),
+    MoveTime(u64),
+}
+
+// a side loses once its own score was at or below -score for `moves` of its moves in a row
+#[derive(Clone, Copy, Debug)]
+pub struct ResignAdjudication {
+    pub moves: usize,
+    pub score: i32,
+}
+
+// the game is drawn once both scores were within +-score for `moves` plies in a row, from move `move_number` on
+#[derive(Clone, Copy, Debug)]
+pub struct DrawAdjudication {
+    pub move_number: usize,
+    pub moves: usize,
+    pub score: i32,
+}
+
+pub struct MatchConfig {
+    pub engines: [EngineConfig; 2],
+    pub limit: MatchLimit,
+    pub games: usize,
+    pub concurrency: usize,
+    pub openings: Vec<Opening>,
+    pub pgn_out: Option<String>,
+    pub sprt: Option<Sprt>,
+    pub resign: Option<ResignAdjudication>,
+    pub draw: Option<DrawAdjudication>,
+    // games still going after this many moves are drawn
+    pub max_moves: Option<usize>,
+}
+
+// how a game ended, the result is from white's point of view
+pub struct GameResult {
+    pub result: &'static str,
+    pub termination: &'static str,
+    pub reason: String,
+}
+
+impl GameResult {
+    pub fn win(side: usize, termination: &'static str, reason: String) -> GameResult {
+        GameResult { result: if side == Side::WHITE { "1-0" } else { "0-1" }, termination, reason }
+    }
+
+    pub fn draw(termination: &'static str, reason: &str) -> GameResult {
+        GameResult { result: "1/2-1/2", termination, reason: reason.to_string() }
+    }
+}
+
+// split a command on whitespace, double quotes keep option names with spaces together
+// e.g. "option1.Automatic Threads"=false
+pub fn tokenize(cmd: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    for char in cmd.chars() {
+        match char {
+            '"' => quoted = !quoted,
+            _ if char.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            _ => token.push(char),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn parse_value<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.parse::<T>().map_err(|_| format!("invalid value \"{}\" for {}", value, key))
+}
+
+// parse a comma separated list of numbers, e.g. "0,5" for sprt
+fn parse_list<T: std::str::FromStr>(key: &str, value: &str, count: usize) -> Result<Vec<T>, String> {
+    let values = value.split(',').map(|value| parse_value::<T>(key, value.trim())).collect::<Result<Vec<T>, String>>()?;
+    if values.len() != count {
+        return Err(format!("expected {} comma separated values for {}, got \"{}\"", count, key, value));
+    }
+    Ok(values)
+}
+
+fn seconds_to_ms(key: &str, seconds: &str) -> Result<u64, String> {
+    let seconds = parse_value::<f64>(key, seconds)?;
+    if seconds < 0.0 {
+        return Err(format!("{} can't be negative", key));
+    }
+    Ok((seconds * 1000.0).round() as u64)
+}
+
+impl MatchLimit {
+    fn time_control_tag(&self) -> String {
+        match self {
+            MatchLimit::Clock(base, increment) => format!("{}+{}", *base as f64 / 1000.0, *increment as f64 / 1000.0),
+            MatchLimit::MoveTime(movetime) => format!("{}/move", *movetime as f64 / 1000.0),
+            MatchLimit::Nodes(_) | MatchLimit::Depth(_) => "-".to_string(),
+        }
+    }
+}
+
+impl MatchConfig {
+    // parse the key=value arguments of the match command
+    pub fn parse(args: &[String]) -> Result<MatchConfig, String> {
+        // play against ourselves unless told otherwise
+        let own_binary = std::env::current_exe().map_err(|error| format!("failed to find the engine binary: {}", error))?;
+        let engine = EngineConfig { name: String::new(), command: own_binary.to_string_lossy().to_string(), options: Vec::new() };
+
+        let mut config = MatchConfig {
+            engines: [engine.clone(), engine],
+            limit: MatchLimit::Clock(10000, 100),
+            games: 2,
+            concurrency: 1,
+            openings: Vec::new(),
+            pgn_out: None,
+            sprt: None,
+            resign: None,
+            draw: None,
+            max_moves: None,
+        };
+        let mut sprt = Sprt { elo0: 0.0, elo1: 5.0, alpha: 0.05, beta: 0.05 };
+        let mut use_sprt = false;
+
+        for arg in args {
+            let (key, value) = arg.split_once('=').ok_or(format!("expected key=value, got \"{}\"", arg))?;
+
+            // engine options keep the case of the option name
+            let option = key.strip_prefix("option1.").map(|name| (0, name)).or(key.strip_prefix("option2.").map(|name| (1, name)));
+            if let Some((index, name)) = option {
+                config.engines[index].options.push((name.to_string(), value.to_string()));
+                continue;
+            }
+
+            match key.to_lowercase().as_str() {
+                "engine1" => config.engines[0].command = value.to_string(),
+                "engine2" => config.engines[1].command = value.to_string(),
+                "name1" => config.engines[0].name = value.to_string(),
+                "name2" => config.engines[1].name = value.to_string(),
+                "tc" => {
+                    let (base, increment) = value.split_once('+').unwrap_or((value, "0"));
+                    config.limit = MatchLimit::Clock(seconds_to_ms(key, base)?, seconds_to_ms(key, increment)?);
+                }
+                "nodes" => config.limit = MatchLimit::Nodes(parse_value(key, value)?),
+                "depth" => config.limit = MatchLimit::Depth(parse_value(key, value)?),
+                "movetime" => config.limit = MatchLimit::MoveTime(parse_value(key, value)?),
+                "games" => config.games = parse_value(key, value)?,
+                "concurrency" => config.concurrency = parse_value(key, value)?,
+                "openings" => config.openings = load_openings(value)?,
+                "pgnout" => config.pgn_out = Some(value.to_string()),
+                "sprt" => {
+                    let elos = parse_list::<f64>(key, value, 2)?;
+                    sprt.elo0 = elos[0];
+                    sprt.elo1 = elos[1];
+                    use_sprt = true;
+                }
+                "alpha" => sprt.alpha = parse_value(key, value)?,
+                "beta" => sprt.beta = parse_value(key, value)?,
+                "resign" => {
+                    let values = parse_list::<i32>(key, value, 2)?;
+                    config.resign = Some(ResignAdjudication { moves: values[0].max(1) as usize, score: values[1] });
+                }
+                "draw" => {
+                    let values = parse_list::<i32>(key, value, 3)?;
+                    config.draw = Some(DrawAdjudication { move_number: values[0].max(0) as usize, moves: values[1].max(1) as usize, score: values[2] });
+                }
+                "maxmoves" => config.max_moves = Some(parse_value(key, value)?),
+                _ => return Err(format!("unknown match setting \"{}\"", key)),
+            }
+        }
+
+        if config.games == 0 || config.concurrency == 0 {
+            return Err("games and concurrency have to be at least 1".to_string());
+        }
+        if use_sprt {
+            if sprt.elo0 >= sprt.elo1 {
+                return Err("sprt needs elo0 < elo1".to_string());
+            }
+            if !(0.0..1.0).contains(&sprt.alpha) || !(0.0..1.0).contains(&sprt.beta) || sprt.alpha == 0.0 || sprt.beta == 0.0 {
+                return Err("alpha and beta have to be between 0 and 1".to_string());
+            }
+            config.sprt = Some(sprt);
+        }
+        if config.openings.is_empty() {
+            config.openings.push(Opening { fen: START_FEN.to_string(), moves: Vec::new() });
+        }
+        Ok(config)
+    }
+}
+
+// games that can't be won anymore, bare kings or a single minor piece
+fn is_dead_draw(position: &Position) -> bool {
+    let heavy = [Piece::WhitePawn, Piece::WhiteRook, Piece::WhiteQueen, Piece::BlackPawn, Piece::BlackRook, Piece::BlackQueen];
+    if heavy.into_iter().any(|piece| position.bitboards[piece as usize].count() != 0) {
+        return false;
+    }
+    let minors = [Piece::WhiteKnight, Piece::WhiteBishop, Piece::BlackKnight, Piece::BlackBishop];
+    minors.into_iter().map(|piece| position.bitboards[piece as usize].count()).sum::<u32>() <= 1
+}
+
+// check if the game ended by the rules
+pub fn game_over(position: &Position) -> Option<GameResult> {
+    if position.count_legal_moves() == 0 {
+        if position.checkers() != 0 {
+            let winner = position.side ^ 1;
+            return Some(GameResult::win(winner, "normal", format!("{} mates", COLOUR_NAMES[winner])));
+        }
+        return Some(GameResult::draw("normal", "Draw by stalemate"));
+    }
+    if position.is_fifty() {
+        return Some(GameResult::draw("normal", "Draw by fifty moves rule"));
+    }
+    if position.is_repetition(0) {
+        return Some(GameResult::draw("normal", "Draw by 3-fold repetition"));
+    }
+    if is_dead_draw(position) {
+        return Some(GameResult::draw("normal", "Draw by insufficient mating material"));
+    }
+    None
+}
+
+// find the legal move the engine played, 0 if it isn't legal
+pub fn find_move(position: &Position, move_string: &str) -> u32 {
+    let mut move_list = MoveList::new();
+    position.generate_moves(GenType::All, &mut move_list);
+    move_list.as_slice().iter().find(|move_| move_.to_string() == move_string).map(|move_| move_.0).unwrap_or(0)
+}
+
+// play one game between the engines, `players` is indexed by colour
+// returns the moves played (including the opening) and the result, None if the match was stopped
+fn play_game(config: &MatchConfig, players: [&mut Engine; 2], opening: &Opening, stop: &AtomicBool) -> Option<(Vec<u32>, GameResult)> {
+    // `load_openings` already dropped the positions that can't be parsed or played
+    let mut position = Position::from_fen_lenient(&opening.fen).expect("openings are validated when they are loaded");
+    let mut moves = Vec::with_capacity(opening.moves.len() + 200);
+    let mut uci_moves = String::new();
+    for &move_ in opening.moves.iter() {
+        position.make(move_);
+        moves.push(move_);
+        uci_moves.push_str(&format!(" {}", Move(move_)));
+    }
+
+    let (mut clocks, increment) = match config.limit {
+        MatchLimit::Clock(base, increment) => ([base as i64; 2], increment as i64),
+        _ => ([0; 2], 0),
+    };
+    let mut resign_counts = [0; 2];
+    let mut draw_count = 0;
+
+    if let Some(result) = game_over(&position) {
+        return Some((moves, result));
+    }
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        let side = position.side;
+        let opponent = side ^ 1;
+
+        let position_cmd = if uci_moves.is_empty() {
+            format!("position fen {}", opening.fen)
+        } else {
+            format!("position fen {} moves{}", opening.fen, uci_moves)
+        };
+        let (go_cmd, timeout) = match config.limit {
+            MatchLimit::Clock(_, _) => (
+                format!("go wtime {} btime {} winc {} binc {}", clocks[Side::WHITE], clocks[Side::BLACK], increment, increment),
+                Duration::from_millis(clocks[side] as u64) + CLOCK_GRACE,
+            ),
+            MatchLimit::Nodes(nodes) => (format!("go nodes {}", nodes), STALL_TIMEOUT),
+            MatchLimit::Depth(depth) => (format!("go depth {}", depth), STALL_TIMEOUT),
+            MatchLimit::MoveTime(movetime) => (format!("go movetime {}", movetime), Duration::from_millis(movetime) + STALL_TIMEOUT),
+        };
+
+        let search = match players[side].go(&position_cmd, &go_cmd, timeout) {
+            Some(search) => search,
+            None => {
+                // make sure a late "bestmove" doesn't end up in the next game
+                players[side].send("stop");
+                let reason = format!("{} {}", COLOUR_NAMES[side], if matches!(config.limit, MatchLimit::Clock(_, _)) { "loses on time" } else { "stalls" });
+                return Some((moves, GameResult::win(opponent, "time forfeit", reason)));
+            }
+        };
+
+        if let MatchLimit::Clock(_, _) = config.limit {
+            clocks[side] -= search.time.as_millis() as i64;
+            if clocks[side] < 0 {
+                return Some((moves, GameResult::win(opponent, "time forfeit", format!("{} loses on time", COLOUR_NAMES[side]))));
+            }
+            clocks[side] += increment;
+        }
+
+        let move_ = find_move(&position, &search.best_move);
+        if move_ == 0 {
+            let reason = format!("{} makes an illegal move: {}", COLOUR_NAMES[side], search.best_move);
+            return Some((moves, GameResult::win(opponent, "rules infraction", reason)));
+        }
+        let move_number = position.fullmove as usize;
+        position.make(move_);
+        moves.push(move_);
+        uci_moves.push_str(&format!(" {}", Move(move_)));
+
+        if let Some(result) = game_over(&position) {
+            return Some((moves, result));
+        }
+
+        // adjudication, a search without a score resets the counters
+        if let Some(resign) = config.resign {
+            match search.score {
+                Some(score) if score <= -resign.score => resign_counts[side] += 1,
+                _ => resign_counts[side] = 0,
+            }
+            if resign_counts[side] >= resign.moves {
+                return Some((moves, GameResult::win(opponent, "adjudication", format!("{} resigns", COLOUR_NAMES[side]))));
+            }
+        }
+        if let Some(draw) = config.draw {
+            match search.score {
+                Some(score) if move_number >= draw.move_number && score.abs() <= draw.score => draw_count += 1,
+                _ => draw_count = 0,
+            }
+            if draw_count >= draw.moves {
+                return Some((moves, GameResult::draw("adjudication", "Draw by adjudication")));
+            }
+        }
+        if let Some(max_moves) = config.max_moves {
+            if moves.len() - opening.moves.len() >= max_moves * 2 {
+                return Some((moves, GameResult::draw("adjudication", "Draw by maximum game length")));
+            }
+        }
+    }
+}
+
+// everything the games share, updated after every game
+struct MatchState {
+    score: MatchScore,
+    pgn: Option<File>,
+}
+
+// print the score after a game, returns true once the SPRT reached a decision
+fn report(config: &MatchConfig, score: &MatchScore) -> bool {
+    let names = [&config.engines[0].name, &config.engines[1].name];
+    let points = (score.wins as f64 + score.draws as f64 / 2.0) / score.games() as f64;
+    send!("Score of {} vs {}: {} - {} - {}  [{:.3}] {}", names[0], names[1], score.wins, score.losses, score.draws, points, score.games());
+
+    let (elo, margin) = score.elo();
+    send!("Elo difference: {:.1} +/- {:.1}, LOS: {:.1} %", elo, margin, score.los() * 100.0);
+
+    if let Some(sprt) = config.sprt {
+        let (lower, upper) = sprt.bounds();
+        send!("SPRT: llr {:.2} ({:.1}%), lbound {:.2}, ubound {:.2}", sprt.llr(score), sprt.llr(score) / upper * 100.0, lower, upper);
+        match sprt.result(score) {
+            SprtResult::H0 => send!("SPRT: H0 was accepted"),
+            SprtResult::H1 => send!("SPRT: H1 was accepted"),
+            SprtResult::Continue => return false,
+        }
+        return true;
+    }
+    false
+}
+
+// play games until there are none left or the match was stopped
+fn run_worker(config: &MatchConfig, next_game: &AtomicUsize, stop: &AtomicBool, state: &Mutex<MatchState>) {
+    let mut engines: [Option<Engine>; 2] = [None, None];
+    loop {
+        let game = next_game.fetch_add(1, Ordering::Relaxed);
+        if game >= config.games || stop.load(Ordering::Relaxed) {
+            return;
+        }
+
+        // restart engines that crashed or stopped answering
+        for index in 0..2 {
+            let alive = engines[index].as_mut().map(|engine| engine.new_game().is_ok()).unwrap_or(false);
+            if !alive {
+                engines[index] = None;
+                match Engine::start(&config.engines[index]) {
+                    Ok(engine) => engines[index] = Some(engine),
+                    Err(error) => {
+                        send!("{}", error);
+                        stop.store(true, Ordering::Relaxed);
+                        return;
+                    }
+                }
+            }
+        }
+
+        // every opening is played twice, with the engines swapping colours
+        let opening = &config.openings[(game / 2) % config.openings.len()];
+        let white = game % 2;
+        let [first, second] = &mut engines;
+        let (first, second) = (first.as_mut().unwrap(), second.as_mut().unwrap());
+        let players = if white == 0 { [first, second] } else { [second, first] };
+
+        let (moves, result) = match play_game(config, players, opening, stop) {
+            Some(game) => game,
+            None => return,
+        };
+
+        let names = [&config.engines[white].name, &config.engines[white ^ 1].name];
+        let record = GameRecord {
+            event: format!("{} vs {}", config.engines[0].name, config.engines[1].name),
+            round: game + 1,
+            white: names[0].clone(),
+            black: names[1].clone(),
+            fen: opening.fen.clone(),
+            moves,
+            result: result.result,
+            termination: result.termination.to_string(),
+            time_control: config.limit.time_control_tag(),
+        };
+
+        let mut state = state.lock().unwrap();
+        // games that finish after the SPRT ended the match don't count
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        // the score is from the point of view of the first engine
+        match (result.result, white) {
+            ("1-0", 0) | ("0-1", 1) => state.score.wins += 1,
+            ("1-0", 1) | ("0-1", 0) => state.score.losses += 1,
+            _ => state.score.draws += 1,
+        }
+        if let Some(file) = state.pgn.as_mut() {
+            if let Err(error) = file.write_all(record.to_pgn().as_bytes()) {
+                send!("failed to write the game to the PGN file: {}", error);
+            }
+        }
+
+        send!("Finished game {} ({} vs {}): {} {{{}}}", game + 1, names[0], names[1], result.result, result.reason);
+        if report(config, &state.score) {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+// the "match" command, plays the two engines against each other and reports the Elo difference
+pub fn run_match(args: &[String]) {
+    match MatchConfig::parse(args) {
+        Ok(config) => {
+            play_match(config);
+        }
+        Err(error) => send!("{}\n{}", error, MATCH_USAGE),
+    }
+}
+
+// play a match and report on it, returns the score of the first engine or None if the match couldn't start
+pub fn play_match(mut config: MatchConfig) -> Option<MatchScore> {
+    // start both engines once to check they work and to get their names
+    for index in 0..2 {
+        match Engine::start(&config.engines[index]) {
+            Ok(engine) => config.engines[index].name = engine.name.clone(),
+            Err(error) => {
+                send!("{}", error);
+                return None;
+            }
+        }
+    }
+    if config.engines[0].name == config.engines[1].name {
+        for index in 0..2 {
+            config.engines[index].name = format!("{} {}", config.engines[index].name, index + 1);
+        }
+    }
+
+    let pgn = match config.pgn_out.as_ref() {
+        Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(file),
+            Err(error) => {
+                send!("failed to open {}: {}", path, error);
+                return None;
+            }
+        },
+        None => None,
+    };
+
+    send!(
+        "Started match of {} games between {} and {} with {} opening(s)",
+        config.games, config.engines[0].name, config.engines[1].name, config.openings.len()
+    );
+
+    let config = Arc::new(config);
+    let next_game = Arc::new(AtomicUsize::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let state = Arc::new(Mutex::new(MatchState { score: MatchScore::default(), pgn }));
+
+    let workers: Vec<_> = (0..config.concurrency.min(config.games)).map(|_| {
+        let (config, next_game, stop, state) = (config.clone(), next_game.clone(), stop.clone(), state.clone());
+        thread::spawn(move || run_worker(&config, &next_game, &stop, &state))
+    }).collect();
+    for worker in workers {
+        let _ = worker.join();
+    }
+
+    let score = state.lock().unwrap().score;
+    send!("Finished match");
+    if score.games() == 0 {
+        send!("No games were finished");
+    }
+    Some(score)
+}
@@ -0,0 +1,164 @@
+// match statistics from the point of view of the first engine
+#[derive(Clone, Copy, Default, Debug)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+// sequential probability ratio test between two Elo hypotheses
+#[derive(Clone, Copy, Debug)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SprtResult {
+    H0,
+    H1,
+    Continue,
+}
+
+// expected score for an Elo difference
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+// Elo difference for an expected score
+fn score_to_elo(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+// error function, Abramowitz and Stegun 7.1.26 (max error 1.5e-7)
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let y = 1.0 - (((((1.061405429 * t - 1.453152027) * t) + 1.421413741) * t - 0.284496736) * t + 0.254829592) * t * (-x * x).exp();
+    if x >= 0.0 { y } else { -y }
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    // average score per game and its variance
+    fn score_variance(&self) -> (f64, f64) {
+        let games = self.games() as f64;
+        let (wins, losses, draws) = (self.wins as f64 / games, self.losses as f64 / games, self.draws as f64 / games);
+        let score = wins + draws / 2.0;
+        let variance = wins * (1.0 - score).powi(2) + draws * (0.5 - score).powi(2) + losses * score.powi(2);
+        (score, variance)
+    }
+
+    // Elo difference and its 95% error margin
+    pub fn elo(&self) -> (f64, f64) {
+        if self.games() == 0 {
+            return (0.0, 0.0);
+        }
+        let (score, variance) = self.score_variance();
+        let deviation = (variance / self.games() as f64).sqrt();
+        // keep away from 0 and 1, where the Elo difference is infinite
+        let clamp = |score: f64| score.clamp(1e-6, 1.0 - 1e-6);
+        let upper = score_to_elo(clamp(score + 1.959964 * deviation));
+        let lower = score_to_elo(clamp(score - 1.959964 * deviation));
+        (score_to_elo(clamp(score)), (upper - lower) / 2.0)
+    }
+
+    // likelihood of superiority, draws don't matter
+    pub fn los(&self) -> f64 {
+        if self.wins + self.losses == 0 {
+            return 0.5;
+        }
+        0.5 * (1.0 + erf((self.wins as f64 - self.losses as f64) / (2.0 * (self.wins + self.losses) as f64).sqrt()))
+    }
+}
+
+// count added to every outcome, so a match without wins, losses or draws still has a finite ratio
+const REGULARISATION: f64 = 1e-3;
+// game results from the point of view of the first engine: loss, draw, win
+const OUTCOMES: [f64; 3] = [0.0, 0.5, 1.0];
+
+// maximum likelihood distribution over the outcomes with the given expected score, closest to the
+// observed frequencies; solves sum(freq * (outcome - score) / (1 + x * (outcome - score))) = 0
+fn mle_distribution(frequencies: &[f64; 3], score: f64) -> [f64; 3] {
+    let residual = |x: f64| -> f64 {
+        frequencies.iter().zip(OUTCOMES).map(|(freq, outcome)| freq * (outcome - score) / (1.0 + x * (outcome - score))).sum()
+    };
+    // every 1 + x * (outcome - score) has to stay positive, and the residual falls as x rises
+    let (mut low, mut high) = (-1.0 / (1.0 - score) + 1e-9, 1.0 / score - 1e-9);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if residual(mid) > 0.0 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    let x = (low + high) / 2.0;
+    let mut distribution = [0.0; 3];
+    for i in 0..3 {
+        distribution[i] = frequencies[i] / (1.0 + x * (OUTCOMES[i] - score));
+    }
+    distribution
+}
+
+impl Sprt {
+    // generalized log-likelihood ratio of H1 against H0 for the trinomial (loss/draw/win) model
+    pub fn llr(&self, score: &MatchScore) -> f64 {
+        if score.games() == 0 {
+            return 0.0;
+        }
+        let counts = [score.losses as f64 + REGULARISATION, score.draws as f64 + REGULARISATION, score.wins as f64 + REGULARISATION];
+        let total: f64 = counts.iter().sum();
+        let frequencies = [counts[0] / total, counts[1] / total, counts[2] / total];
+
+        let p0 = mle_distribution(&frequencies, expected_score(self.elo0));
+        let p1 = mle_distribution(&frequencies, expected_score(self.elo1));
+        (0..3).map(|i| counts[i] * (p1[i] / p0[i]).ln()).sum()
+    }
+
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    pub fn result(&self, score: &MatchScore) -> SprtResult {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            return SprtResult::H1;
+        } else if llr <= lower {
+            return SprtResult::H0;
+        }
+        SprtResult::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPRT: Sprt = Sprt { elo0: 0.0, elo1: 5.0, alpha: 0.05, beta: 0.05 };
+
+    #[test]
+    fn llr_matches_the_normal_approximation() {
+        let score = MatchScore { wins: 1200, losses: 1100, draws: 2700 };
+        let (mean, variance) = score.score_variance();
+        let (score0, score1) = (expected_score(SPRT.elo0), expected_score(SPRT.elo1));
+        let normal = score.games() as f64 * (score1 - score0) * (2.0 * mean - score0 - score1) / (2.0 * variance);
+        assert!((SPRT.llr(&score) - normal).abs() < 0.01, "{} {}", SPRT.llr(&score), normal);
+    }
+
+    #[test]
+    fn llr_without_wins_or_losses() {
+        assert_eq!(SPRT.llr(&MatchScore::default()), 0.0);
+        // one-sided results still move the test in the right direction
+        assert!(SPRT.llr(&MatchScore { wins: 20, losses: 0, draws: 10 }) > 0.0);
+        assert!(SPRT.llr(&MatchScore { wins: 0, losses: 20, draws: 10 }) < 0.0);
+        assert!(SPRT.llr(&MatchScore { wins: 0, losses: 0, draws: 30 }).is_finite());
+        assert_eq!(SPRT.result(&MatchScore { wins: 400, losses: 0, draws: 0 }), SprtResult::H1);
+        assert_eq!(SPRT.result(&MatchScore { wins: 0, losses: 400, draws: 0 }), SprtResult::H0);
+    }
+}
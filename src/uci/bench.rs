@@ -0,0 +1,55 @@
+use crate::board::position::*;
+use crate::search::*;
+
+use std::time::Instant;
+use std::ptr::addr_of_mut;
+
+pub const BENCH_DEPTH: u8 = 10;
+
+// fixed set of positions searched by the "bench" command
+#[rustfmt::skip]
+pub const BENCH_POSITIONS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r2q1rk1/ppp2ppp/2n1bn2/2b1p3/3pP3/3P1NPP/PPP1NPB1/R1BQ1RK1 b - - 0 9",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "2r3k1/pp3ppp/2n1b3/3p4/3P4/2PB1N2/P4PPP/R5K1 w - - 0 20",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+];
+
+// search the bench positions to a fixed depth and report the speed of the search
+pub fn bench(depth: u8) {
+    // single threaded so the node count is deterministic
+    let (threads, threads_automatic) = unsafe { (OPTIONS.threads, OPTIONS.threads_automatic) };
+    unsafe {
+        OPTIONS.threads = 1;
+        OPTIONS.threads_automatic = false;
+    }
+
+    let mut nodes = 0;
+    let start = Instant::now();
+    for fen in BENCH_POSITIONS.iter() {
+        let mut position = Position::from_fen(fen).unwrap();
+        let mut searcher = Searcher::new();
+        // a clock that never runs out, so polling it is part of the measurement like in a real game
+        searcher.time_manager.movetime = i32::MAX;
+        searcher.time_manager.init(0, false);
+
+        unsafe { (*addr_of_mut!(TT)).reset(); }
+        searcher.search_position(&mut position, depth);
+        nodes += searcher.nodes;
+    }
+    let time = start.elapsed().as_millis().max(1);
+
+    unsafe {
+        OPTIONS.threads = threads;
+        OPTIONS.threads_automatic = threads_automatic;
+    }
+
+    send!("\n   Depth: {}", depth);
+    send!("   Nodes: {}", nodes);
+    send!("    Time: {} ms", time);
+    send!("     NPS: {}", nodes as u128 * 1000 / time);
+}
@@ -0,0 +1,113 @@
+use crate::board::position::*;
+use crate::tools::*;
+use crate::uci::*;
+
+pub const CLI_USAGE: &str = "usage: hydrochess [--config <file>] [command]
+
+commands:
+    uci                                   start the UCI loop (default)
+    bench [depth]                         search the bench positions to a fixed depth
+    perft <depth> [fen]                   count the leaf nodes of the move tree
+    search [--fen <fen>] [--depth <n>] [--nodes <n>] [--movetime <ms>]
+                                          search a position and print the best move
+    match <key=value ...>                 play two engines against each other
+    datagen <key=value ...>               generate training data
+    dataconvert <binary file> <text file> convert training data to text
+    calibrate <key=value ...>             measure the strength of the skill levels
+    help                                  print this message";
+
+// the value following a flag, FENs can be given as one quoted argument or as separate words
+fn flag_value(args: &[String], index: usize) -> Option<String> {
+    let words: Vec<&str> = args[index + 1..].iter().take_while(|arg| !arg.starts_with("--")).map(|arg| arg.as_str()).collect();
+    if words.is_empty() { None } else { Some(words.join(" ")) }
+}
+
+fn parse_position_arg(fen: Option<String>) -> Option<Position> {
+    let fen = match fen {
+        Some(fen) => fen,
+        None => return Some(Position::new()),
+    };
+    let position = Position::from_fen_lenient(&fen).and_then(|position| position.validate().map(|_| position));
+    match position {
+        Ok(position) => Some(position),
+        Err(error) => {
+            send!("Invalid fen given: {}", error);
+            None
+        }
+    }
+}
+
+// "search --fen <fen> --depth <n>", turned into a "go" command for the position
+fn run_search(args: &[String]) {
+    let mut fen = None;
+    let mut go = String::from("go");
+    for (index, arg) in args.iter().enumerate() {
+        match arg.as_str() {
+            "--fen" => fen = flag_value(args, index),
+            "--depth" | "--nodes" | "--movetime" => match flag_value(args, index) {
+                Some(value) if value.parse::<u64>().is_ok() => go.push_str(&format!(" {} {}", &arg[2..], value)),
+                _ => {
+                    send!("{} needs a number\n{}", arg, CLI_USAGE);
+                    return;
+                }
+            },
+            _ if arg.starts_with("--") => {
+                send!("unknown flag {}\n{}", arg, CLI_USAGE);
+                return;
+            }
+            _ => (),
+        }
+    }
+    if go == "go" {
+        go.push_str(&format!(" depth {}", BENCH_DEPTH));
+    }
+
+    if let Some(mut position) = parse_position_arg(fen) {
+        position.parse_go(&go);
+    }
+}
+
+// handle the command line, the config file is applied before any command runs
+pub fn run_cli(args: &[String]) {
+    let mut args = args.to_vec();
+    let mut config = None;
+    if let Some(index) = args.iter().position(|arg| arg == "--config") {
+        if index + 1 >= args.len() {
+            send!("--config needs a file\n{}", CLI_USAGE);
+            return;
+        }
+        config = Some(args.remove(index + 1));
+        args.remove(index);
+    }
+    apply_config(config.as_deref());
+
+    let command = args.first().map(|arg| arg.to_lowercase()).unwrap_or_else(|| "uci".to_string());
+    let rest = if args.is_empty() { &args[..] } else { &args[1..] };
+    match command.as_str() {
+        "uci" => main_loop(),
+        "bench" => {
+            let depth = rest.first().and_then(|depth| depth.parse::<u8>().ok()).unwrap_or(BENCH_DEPTH);
+            bench(depth);
+        }
+        "perft" => {
+            let depth = match rest.first().and_then(|depth| depth.parse::<u32>().ok()) {
+                Some(depth) if depth > 0 => depth,
+                _ => {
+                    send!("perft needs a depth of at least 1\n{}", CLI_USAGE);
+                    return;
+                }
+            };
+            let fen = if rest.len() > 1 { Some(rest[1..].join(" ")) } else { None };
+            if let Some(mut position) = parse_position_arg(fen) {
+                perft_test(&mut position, depth);
+            }
+        }
+        "search" => run_search(rest),
+        "match" => run_match(rest),
+        "datagen" => run_datagen(rest),
+        "dataconvert" => convert_data(rest),
+        "calibrate" => run_calibration(rest),
+        "help" | "--help" | "-h" => send!("{}", CLI_USAGE),
+        _ => send!("unknown command {}\n{}", command, CLI_USAGE),
+    }
+}
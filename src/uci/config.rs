@@ -0,0 +1,91 @@
+use crate::evaluation::*;
+use crate::uci::*;
+
+use std::fs;
+use std::path::Path;
+
+// config files looked for in the working directory when none is given on the command line
+pub const DEFAULT_CONFIG_FILES: [&str; 2] = ["hydrochess.toml", "hydrochess.ini"];
+
+// sections of the config file, keys before the first section are UCI options too
+const SECTIONS: [&str; 5] = ["options", "uci", "search", "evaluation", "eval"];
+
+// paths the engine knows about but can't use yet
+const UNSUPPORTED_KEYS: [(&str, &str); 4] = [
+    ("book", "opening books"),
+    ("bookfile", "opening books"),
+    ("syzygypath", "tablebases"),
+    ("tablebases", "tablebases"),
+];
+
+// load a TOML/INI style config file and apply it, e.g.
+//
+//     # UCI options, the same names as in "setoption"
+//     Hash = 64
+//     Threads = 4
+//     Move Overhead = 30
+//
+//     [search]
+//     lmr_base = 80
+//
+//     [evaluation]
+//     bishop_pair = 20
+//
+// keys outside of [evaluation] are set as UCI options, keys in [evaluation] set the evaluation weights
+pub fn load_config(path: &str) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("failed to read {}: {}", path, error))?;
+    let mut section = String::new();
+
+    for (number, line) in text.lines().enumerate() {
+        // strip comments and whitespace
+        let line = line.split(|char| char == '#' || char == ';').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |message: &str| format!("{} line {}: {}", path, number + 1, message);
+
+        if let Some(name) = line.strip_prefix('[') {
+            section = name.strip_suffix(']').ok_or(error("unterminated section"))?.trim().to_lowercase();
+            if !SECTIONS.contains(&section.as_str()) {
+                return Err(error(&format!("unknown section [{}]", section)));
+            }
+            continue;
+        }
+
+        let (key, value) = line.split_once('=').or(line.split_once(':')).ok_or(error("expected key = value"))?;
+        let key = key.trim().trim_matches('"');
+        // TOML strings are quoted, INI values aren't
+        let value = value.trim().trim_matches('"').trim_matches('\'');
+
+        match section.as_str() {
+            "evaluation" | "eval" => {
+                let value = value.parse::<i16>().map_err(|_| error(&format!("invalid value \"{}\" for {}", value, key)))?;
+                if !set_eval_weight(key, value) {
+                    return Err(error(&format!("unknown evaluation weight \"{}\"", key)));
+                }
+            }
+            _ => {
+                if let Some((_, feature)) = UNSUPPORTED_KEYS.iter().find(|(name, _)| key.to_lowercase().replace(' ', "") == *name) {
+                    send!("info string {} is ignored, HydroChess doesn't support {} yet", key, feature);
+                    continue;
+                }
+                parse_option(&format!("setoption name {} value {}", key, value));
+            }
+        }
+    }
+    Ok(())
+}
+
+// apply the config file given on the command line, or the first default one that exists
+pub fn apply_config(path: Option<&str>) {
+    let path = match path {
+        Some(path) => path,
+        None => match DEFAULT_CONFIG_FILES.iter().find(|path| Path::new(path).exists()) {
+            Some(path) => path,
+            None => return,
+        },
+    };
+    if let Err(error) = load_config(path) {
+        send!("info string {}", error);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::board::position::*;
+use crate::search::*;
+use crate::uci::interface::UciEvent;
+
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Sender;
+use std::thread::{self, JoinHandle};
+
+// the searcher and the negamax recursion live on the stack of the search thread
+const SEARCH_STACK_SIZE: usize = 16 * 1024 * 1024;
+
+// owns the thread running the current "go" command, so there is never more than one search at a time
+pub struct SearchController {
+    worker: Option<JoinHandle<()>>,
+    // the main loop is told when a search is over, so it can apply the commands it held back
+    events: Sender<UciEvent>,
+}
+
+impl SearchController {
+    pub fn new(events: Sender<UciEvent>) -> SearchController {
+        SearchController { worker: None, events }
+    }
+
+    // true until the search has sent its bestmove, the thread may take a moment longer to exit
+    pub fn is_searching(&self) -> bool {
+        self.worker.as_ref().is_some_and(|worker| !worker.is_finished()) && SEARCHING.load(Ordering::Relaxed)
+    }
+
+    // search a copy of the position on the worker thread, after the previous search has finished
+    pub fn start(&mut self, position: &Position, cmd: &str) {
+        self.wait();
+        // set before spawning the search so an early "stop" or "ponderhit" can't get lost
+        STOP.store(false, Ordering::Relaxed);
+        SEARCHING.store(true, Ordering::Relaxed);
+        PONDER.store(cmd.split_whitespace().any(|token| token == "ponder"), Ordering::Relaxed);
+
+        let mut position = position.clone();
+        let cmd = cmd.to_string();
+        let events = self.events.clone();
+        let worker = thread::Builder::new().name("search".to_string()).stack_size(SEARCH_STACK_SIZE).spawn(move || {
+            position.parse_go(&cmd);
+            // nobody is listening any more after "quit"
+            let _ = events.send(UciEvent::SearchDone);
+        });
+        match worker {
+            Ok(worker) => self.worker = Some(worker),
+            Err(error) => {
+                SEARCHING.store(false, Ordering::Relaxed);
+                send!("info string failed to start the search: {}", error);
+            }
+        }
+    }
+
+    // stop the search and wait for it to send its bestmove
+    pub fn stop(&mut self) {
+        if self.worker.is_some() {
+            STOP.store(true, Ordering::Relaxed);
+        }
+        self.wait();
+    }
+
+    // wait for the search thread to exit
+    pub fn wait(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            if worker.join().is_err() {
+                send!("info string the search thread panicked");
+            }
+        }
+        // a "stop" only applies to the search it was sent for
+        STOP.store(false, Ordering::Relaxed);
+    }
+}
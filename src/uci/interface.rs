@@ -1,84 +1,179 @@
-use std::fs::OpenOptions;
 use std::io;
 use std::io::Write;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
 
 // use crate::r#move::movegen::*;
 use crate::board::position::*;
+use crate::evaluation::*;
 use crate::search::*;
 // use crate::r#move::encode::*;
+use crate::tools::*;
 use crate::uci::*;
+use std::ptr::addr_of_mut;
 
 const NAME: &str = "HydroChess";
 const AUTHOR: &str = "FirePlank";
 pub const SUPPORTED_VARIANTS: [&str; 2] = ["chess", "standard"];
 
-// main UCI loop
-pub fn main_loop() {
-    let mut cmd = String::new();
-    let mut position = Position::new();
+// commands that change what the search reads, held back until the search is over
+const QUEUED_COMMANDS: [&str; 3] = ["setoption", "position", "ucinewgame"];
+// commands that would run a second search next to the current one
+const SEARCH_COMMANDS: [&str; 2] = ["go", "bench"];
+// commands that are never held back, UCI wants "isready" answered right away even during a search
+const IMMEDIATE_COMMANDS: [&str; 4] = ["stop", "ponderhit", "quit", "isready"];
 
-    println!("{} by {}", NAME, AUTHOR);
+// what the main loop waits for
+pub enum UciEvent {
+    // a line from the GUI
+    Input(String),
+    // the GUI closed the pipe
+    InputClosed,
+    // a search thread is about to exit
+    SearchDone,
+}
 
-    loop {
-        // get UCI input inline
-        io::stdout().flush().unwrap_or_else(|error| {
-            println!("info string failed to keep things inline when taking input: {}", error);
-        });
-        io::stdin().read_line(&mut cmd).unwrap_or_else(|error| {
-            println!("info string failed to take UCI input: {}", error);
-            return 0;
+// read the GUI's commands on their own thread, so the main loop also hears about finished searches
+fn spawn_input_reader(events: Sender<UciEvent>) {
+    thread::spawn(move || loop {
+        let mut cmd = String::new();
+        let read = io::stdin().read_line(&mut cmd).unwrap_or_else(|error| {
+            send!("info string failed to take UCI input: {}", error);
+            0
         });
+        let event = if read == 0 { UciEvent::InputClosed } else { UciEvent::Input(cmd) };
+        let closed = matches!(event, UciEvent::InputClosed);
+        // the main loop is gone after "quit"
+        if events.send(event).is_err() || closed {
+            return;
+        }
+    });
+}
 
-        // append cmd to debug.txt on newline
-        // let mut file = OpenOptions::new()
-        //     .append(true)
-        //     .open("debug.txt")
-        //     .unwrap();
+// main UCI loop
+pub fn main_loop() {
+    let mut position = Position::new();
+    let (sender, events): (Sender<UciEvent>, Receiver<UciEvent>) = mpsc::channel();
+    let mut controller = SearchController::new(sender.clone());
+    let mut pending: Vec<String> = Vec::new();
 
-        // file.write_all(cmd.as_bytes()).expect("write failed");
+    send!("{} by {}", NAME, AUTHOR);
+    io::stdout().flush().unwrap_or_else(|error| {
+        send!("info string failed to keep things inline when taking input: {}", error);
+    });
+    spawn_input_reader(sender);
 
-        // handle all the UCI commands
-        match cmd.trim().to_lowercase().split_whitespace().next().unwrap_or_else(|| {
-            println!("info string no command given");
-            return ".";
-        }) {
-            "uci" => {
-                println!("id name {}", NAME);
-                println!("id author {}", AUTHOR);
-                println!("\noption name Threads type spin default 1 min 1 max 65535");
-                println!("option name Automatic Threads type check default true");
-                println!("option name Hash type spin default 32 min 1 max 65535");
-                println!("option name Clear Hash type button");
-                println!("option name UCI_Variant type combo default standard var {}", SUPPORTED_VARIANTS.join(" var "));
-                println!("uciok");
-            },
-            "setoption" => parse_option(&cmd),
-            "position" => {
-                position = parse_position(&cmd);
-            },
-            "ucinewgame" => {
-                position = Position::new();
-                unsafe { 
-                    TT.reset(); 
-                    // REPETITION.iter_mut().for_each(|x| *x = 0);
-                    // REP_INDEX = 0;
+    for event in events.iter() {
+        let cmd = match event {
+            UciEvent::Input(cmd) => cmd,
+            UciEvent::InputClosed => break,
+            UciEvent::SearchDone => {
+                // catch up on the commands that came in during the search, unless the next one already started
+                if !controller.is_searching() {
+                    controller.wait();
+                    if !execute_pending(&mut pending, &mut position, &mut controller) {
+                        break;
+                    }
                 }
-            },
-            "go" => {
-                let mut pos: Position = position.clone();
-                thread::spawn( move || {
-                        pos.parse_go(&cmd);
-                });
-            },
-            "isready" => println!("readyok"),
-            "stop" => unsafe { STOP = true },
-            "quit" => break,
-            "." => (),
-            _ => println!("info string unknown UCI command: {}", cmd)
+                continue;
+            }
+        };
+        log_received(cmd.trim_end());
+
+        let command = cmd.trim().to_lowercase().split_whitespace().next().unwrap_or("").to_string();
+        if controller.is_searching() {
+            if SEARCH_COMMANDS.contains(&command.as_str()) {
+                send!("info string already searching, send \"stop\" first");
+                continue;
+            }
+            // "d" and "eval" are answered right away without stopping the search, unless commands are already
+            // held back, then they wait for those so they don't show a stale position
+            if QUEUED_COMMANDS.contains(&command.as_str()) || (!pending.is_empty() && !IMMEDIATE_COMMANDS.contains(&command.as_str())) {
+                pending.push(cmd);
+                continue;
+            }
+        } else {
+            // the search sent its bestmove but the thread hasn't reported back yet
+            controller.wait();
+            if !execute_pending(&mut pending, &mut position, &mut controller) {
+                break;
+            }
         }
 
-        // reset command string
-        cmd = String::new();
+        if !execute(&cmd, &mut position, &mut controller) {
+            break;
+        }
+    }
+
+    controller.stop();
+}
+
+// run the commands held back during the search, returns false on "quit"
+fn execute_pending(pending: &mut Vec<String>, position: &mut Position, controller: &mut SearchController) -> bool {
+    pending.drain(..).all(|queued| execute(&queued, position, controller))
+}
+
+// handle a single UCI command, returns false on "quit"
+fn execute(cmd: &str, position: &mut Position, controller: &mut SearchController) -> bool {
+    // handle all the UCI commands
+    match cmd.trim().to_lowercase().split_whitespace().next().unwrap_or_else(|| {
+        send!("info string no command given");
+        "."
+    }) {
+        "uci" => {
+            send!("id name {}", NAME);
+            send!("id author {}", AUTHOR);
+            send!("\noption name Threads type spin default 1 min 1 max 65535");
+            send!("option name Automatic Threads type check default true");
+            send!("option name Hash type spin default 32 min 1 max 65535");
+            send!("option name Clear Hash type button");
+            send!("option name Ponder type check default false");
+            send!("option name MultiPV type spin default 1 min 1 max 256");
+            send!("option name Move Overhead type spin default 10 min 0 max 5000");
+            send!("option name Skill Level type spin default {} min 0 max {}", MAX_SKILL_LEVEL, MAX_SKILL_LEVEL);
+            send!("option name UCI_LimitStrength type check default false");
+            send!("option name UCI_Elo type spin default {} min {} max {}", Skill::max_elo(), Skill::min_elo(), Skill::max_elo());
+            send!("option name Debug Log File type string default <empty>");
+            send!("option name UCI_Variant type combo default standard var {}", SUPPORTED_VARIANTS.join(" var "));
+            // search parameters, for tuning
+            for tunable in TUNABLES.iter() {
+                send!("{}", tunable.uci_option());
+            }
+            send!("uciok");
+        },
+        "setoption" => parse_option(cmd),
+        "position" => {
+            *position = parse_position(cmd);
+        },
+        "ucinewgame" => {
+            *position = Position::new();
+            unsafe { 
+                (*addr_of_mut!(TT)).reset(); 
+                // REPETITION.iter_mut().for_each(|x| *x = 0);
+                // REP_INDEX = 0;
+            }
+        },
+        "go" => controller.start(position, cmd),
+        "bench" => {
+            let depth = cmd.split_whitespace().nth(1).and_then(|depth| depth.parse::<u8>().ok()).unwrap_or(BENCH_DEPTH);
+            bench(depth);
+        },
+        "spsa" => print_spsa(),
+        // keep the case of paths and option names
+        "match" => run_match(&tokenize(cmd)[1..]),
+        "datagen" => run_datagen(&tokenize(cmd)[1..]),
+        "dataconvert" => convert_data(&tokenize(cmd)[1..]),
+        "calibrate" => run_calibration(&tokenize(cmd)[1..]),
+        "d" => position.show_debug(),
+        "eval" => print_eval_trace(position),
+        "debug" => DEBUG.store(cmd.split_whitespace().nth(1).is_some_and(|mode| mode.eq_ignore_ascii_case("on")), Ordering::Relaxed),
+        "isready" => send!("readyok"),
+        "ponderhit" => PONDER.store(false, Ordering::Relaxed),
+        "stop" => controller.stop(),
+        "quit" => return false,
+        "." => (),
+        _ => send!("info string unknown UCI command: {}", cmd)
     }
+    true
 }
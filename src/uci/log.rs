@@ -0,0 +1,67 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// the "Debug Log File", None while logging is off
+// output is printed while holding the lock so the log has the same order as stdout
+static LOG: Mutex<Option<File>> = Mutex::new(None);
+
+// print a line to the GUI, and to the debug log if there is one
+macro_rules! send {
+    () => {
+        $crate::uci::log::send_line("")
+    };
+    ($($arg:tt)*) => {
+        $crate::uci::log::send_line(&format!($($arg)*))
+    };
+}
+
+// time of day (UTC) with milliseconds, e.g. 14:03:27.051
+fn timestamp() -> String {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_millis()).unwrap_or(0);
+    let seconds = millis / 1000;
+    format!("{:02}:{:02}:{:02}.{:03}", seconds / 3600 % 24, seconds / 60 % 60, seconds % 60, millis % 1000)
+}
+
+fn write_log(log: &mut Option<File>, direction: &str, line: &str) {
+    if let Some(file) = log.as_mut() {
+        let mut written = Ok(());
+        for line in line.split('\n') {
+            written = written.and_then(|_| writeln!(file, "[{}] {} {}", timestamp(), direction, line));
+        }
+        // flush every line so nothing is lost if the engine crashes
+        if written.and_then(|_| file.flush()).is_err() {
+            // a log that can't be written is turned off instead of failing every line
+            *log = None;
+        }
+    }
+}
+
+// start logging to `path`, appending to the file, an empty path or "<empty>" turns logging off
+pub fn set_log_file(path: &str) -> Result<(), String> {
+    let mut log = LOG.lock().unwrap_or_else(|error| error.into_inner());
+    if path.is_empty() || path == "<empty>" {
+        *log = None;
+        return Ok(());
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path).map_err(|error| format!("failed to open {}: {}", path, error))?;
+    *log = Some(file);
+    Ok(())
+}
+
+// log a line received from the GUI
+pub fn log_received(line: &str) {
+    let mut log = LOG.lock().unwrap_or_else(|error| error.into_inner());
+    write_log(&mut log, ">>", line);
+}
+
+// print a line and log it as sent, use `send!` instead of calling this directly
+pub fn send_line(line: &str) {
+    let mut log = LOG.lock().unwrap_or_else(|error| error.into_inner());
+    let mut stdout = io::stdout().lock();
+    // the GUI going away isn't something we can report anywhere
+    let _ = writeln!(stdout, "{}", line);
+    let _ = stdout.flush();
+    write_log(&mut log, "<<", line);
+}
@@ -1,8 +1,24 @@
+#[macro_use]
+pub mod log;
+pub use log::*;
+
 pub mod perft;
 pub use perft::*;
 
+pub mod bench;
+pub use bench::*;
+
+pub mod controller;
+pub use controller::*;
+
 pub mod interface;
 pub use interface::*;
 
 pub mod uci;
-pub use uci::*;
\ No newline at end of file
+pub use uci::*;
+
+pub mod config;
+pub use config::*;
+
+pub mod cli;
+pub use cli::*;
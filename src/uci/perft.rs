@@ -1,6 +1,7 @@
 use crate::board::position::*;
 use crate::r#move::encode::*;
 use crate::r#move::movegen::*;
+use crate::r#move::generate::*;
 use crate::search::OPTIONS;
 
 use std::time::Instant;
@@ -20,19 +21,22 @@ pub fn perft_driver(position: &mut Position, depth: u32) {
     }
 
     let mut move_list = MoveList::new();
-    position.generate_pseudo_moves(&mut move_list);
+    position.generate_moves(GenType::All, &mut move_list);
+
+    // bulk counting, every generated move is legal
+    if depth == 1 {
+        unsafe {
+            NODES += move_list.count as u64;
+        }
+        return;
+    }
 
     // loop over generated moves
     for move_count in 0..move_list.count {
         // init move
-        let move_ = move_list.moves[move_count as usize];
+        let move_ = move_list.moves[move_count as usize].0;
 
-        let legal = position.make(move_);
-        if !legal {
-            // unmake move
-            position.unmake(move_);
-            continue;
-        }
+        position.make(move_);
         // recurse
         perft_driver(position, depth - 1);
         // unmake move
@@ -42,10 +46,10 @@ pub fn perft_driver(position: &mut Position, depth: u32) {
 
 // perft test
 pub fn perft_test(position: &mut Position, depth: u32) {
-    println!("\n    Performance test\n");
+    send!("\n    Performance test\n");
 
     let mut move_list = MoveList::new();
-    position.generate_pseudo_moves(&mut move_list);
+    position.generate_moves(GenType::All, &mut move_list);
 
     // start timer
     let now = Instant::now();
@@ -53,14 +57,9 @@ pub fn perft_test(position: &mut Position, depth: u32) {
     // loop over generated moves
     for move_count in 0..move_list.count {
         // init move
-        let move_ = move_list.moves[move_count as usize];
+        let move_ = move_list.moves[move_count as usize].0;
 
-        let legal = position.make(move_);
-        if !legal {
-            // unmake move
-            position.unmake(move_);
-            continue;
-        }
+        position.make(move_);
 
         // cummulative nodes
         let commulative_nodes = unsafe { NODES };
@@ -74,20 +73,11 @@ pub fn perft_test(position: &mut Position, depth: u32) {
         // unmake move
         position.unmake(move_);
         // print move
-        let source = source(move_);
-        let target = target(move_);
-        let promoted = promoted(move_);
-        println!(
-            "    move: {}{}{}   nodes: {}",
-            SQUARE_COORDS[source as usize],
-            SQUARE_COORDS[target as usize],
-            PROMOTED_PIECES[promoted as usize],
-            old_nodes,
-        );
+        send!("    move: {}   nodes: {}", Move(move_), old_nodes);
     }
 
     // print results
-    println!("\n   Depth: {}", depth);
-    println!("   Nodes: {}", unsafe { NODES });
-    println!("    Time: {} ms", now.elapsed().as_millis());
+    send!("\n   Depth: {}", depth);
+    send!("   Nodes: {}", unsafe { NODES });
+    send!("    Time: {} ms", now.elapsed().as_millis());
 }
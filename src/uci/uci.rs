@@ -2,74 +2,41 @@ use crate::{r#move::movegen::*, board::position};
 use crate::board::position::*;
 use crate::cache::*;
 use crate::r#move::encode::*;
+use crate::r#move::generate::*;
 use crate::search::*;
 
-use std::time::{UNIX_EPOCH, SystemTime};
+use crate::uci::log::set_log_file;
 use crate::uci::SUPPORTED_VARIANTS;
+use std::ptr::addr_of_mut;
+
+// parameters of the "go" command, used to know where a "searchmoves" list ends
+const GO_PARAMETERS: [&str; 12] = ["searchmoves", "ponder", "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite"];
 
 impl Position {
     // parse user/GUI move string input (eg. "e2e4")
     pub fn parse_uci(&mut self, move_string: &str) -> u32 {
-        // make iterator of lowercased str
-        let move_parse = move_string.to_lowercase();
-        let mut move_parse = move_parse.chars();
+        let parsed = match move_string.parse::<Move>() {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                send!("info string {}", error);
+                return 0;
+            }
+        };
         // create move list
         let mut move_list = MoveList::new();
-        // generate pseudo-legal moves
-        self.generate_pseudo_moves(&mut move_list);
-
-        // parse source square
-        let source_square = *ASCII_TO_SQUARE.get((move_parse.next().unwrap_or('.').to_string()+&move_parse.next().unwrap_or('.').to_string()).as_str()).unwrap_or_else( || {
-            println!("info string Invalid source square given");
-            return &Square::NoSquare;
-        });
-        if source_square == Square::NoSquare {
-            return 0;
-        }
-        // parse target square
-        let target_square = *ASCII_TO_SQUARE.get((move_parse.next().unwrap_or('.').to_string()+&move_parse.next().unwrap_or('.').to_string()).as_str()).unwrap_or_else( || {
-            println!("info string Invalid target square given");
-            return &Square::NoSquare;
-        });
-        if target_square == Square::NoSquare {
-            return 0;
-        }
-        let promotion_string = move_parse.next().unwrap_or_else(|| {
-            return '.';
-        });
-
-        // loop over the moves within a move list
-        for move_count in 0..move_list.count {
-            let move_ = move_list.moves[move_count as usize];
-            // make sure source & target squares are available within the generated move
-            if source_square as u8 == source(move_) && target_square as u8 == target(move_) {
-                // init promoted piece
-                let promoted_piece = promoted(move_);
-                if promoted_piece != 0 {
-                    // check if promotion piece is correct
-                    if promotion_string == '.' { 
-                        println!("info string No promotion piece given");
-                        return 0;
-                    }
-                    // parse promotion piece and check move string for each promotion type
-
-                    // promoted to queen
-                    if ((promoted_piece == Piece::WhiteQueen as u8 || promoted_piece == Piece::BlackQueen as u8) && promotion_string == 'q') ||
-                    // promoted to rook
-                    ((promoted_piece == Piece::WhiteRook as u8 || promoted_piece == Piece::BlackRook as u8) && promotion_string == 'r') ||
-                    // promoted to bishop
-                    ((promoted_piece == Piece::WhiteBishop as u8 || promoted_piece == Piece::BlackBishop as u8) && promotion_string == 'b') ||
-                    // promoted to knight
-                    ((promoted_piece == Piece::WhiteKnight as u8 || promoted_piece == Piece::BlackKnight as u8) && promotion_string == 'n') {
-                        // return move
-                        return move_;
-                    }
+        // generate legal moves
+        self.generate_moves(GenType::All, &mut move_list);
 
-                    // continue loop to try to find promotion piece that matches move string
-                    continue;
+        // find the legal move with the same squares and promotion piece
+        for &move_ in move_list.as_slice() {
+            if move_.source() == parsed.source() && move_.target() == parsed.target() {
+                if move_.promoted() != 0 && parsed.promoted() == 0 {
+                    send!("info string No promotion piece given");
+                    return 0;
+                }
+                if move_.promoted() % 6 == parsed.promoted() {
+                    return move_.0;
                 }
-                // return move if valid
-                return move_;
             }
         }
         return 0;
@@ -79,7 +46,7 @@ impl Position {
     pub fn parse_go(&mut self, cmd: &str) {
         // init error closures
         let error = || {
-            println!("info string Invalid uci command given");
+            send!("info string Invalid uci command given");
             return ".";
         };
         // let error_param = || {
@@ -93,99 +60,119 @@ impl Position {
         let mut depth: u8 = 0;
         // split command by whitespace
         let trimmed = cmd.trim().to_lowercase();
-        let mut split_cmd = trimmed.split_whitespace();
+        let mut split_cmd = trimmed.split_whitespace().peekable();
         split_cmd.next().unwrap_or_else(error);
 
-        let mut searcher: Searcher = Searcher::new();;
+        let mut searcher: Searcher = Searcher::new();
         unsafe {
-        searcher.time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
         loop {
             let next = split_cmd.next().unwrap_or_else(silent);
             if next == "." { break; }
             match next {
                 "depth" => {
                     depth = split_cmd.next().unwrap_or_else(silent).parse::<u8>().unwrap_or_else(|error| {
-                        println!("info string Invalid parameter value given: {}", error);
+                        send!("info string Invalid parameter value given: {}", error);
                         return 0;
                     });
                 }, 
                 "wtime" => {
                     if self.side == 0 {
-                        searcher.playtime = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
-                            println!("info string Invalid parameter value given: {}", error);
+                        searcher.time_manager.playtime = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
+                            send!("info string Invalid parameter value given: {}", error);
                             return -1;
                         });
-                        if searcher.playtime != -1 { searcher.playtime += 7; }
                     }
                 },
                 "btime" => {
                     if self.side == 1 {
-                        searcher.playtime = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
-                            println!("info string Invalid parameter value given: {}", error);
+                        searcher.time_manager.playtime = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
+                            send!("info string Invalid parameter value given: {}", error);
                             return -1;
                         });
-                        if searcher.playtime != -1 { searcher.playtime += 7; }
                     }
                 },
                 "winc" => {
                     if self.side == 0 {
-                        searcher.inc = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
-                            println!("info string Invalid parameter value given: {}", error);
+                        searcher.time_manager.inc = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
+                            send!("info string Invalid parameter value given: {}", error);
                             return 0;
                         });
-                        if searcher.inc != 0 { searcher.inc += 6; }
                     }
                 },
                 "binc" => {
                     if self.side == 1 {
-                        searcher.inc = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
-                            println!("info string Invalid parameter value given: {}", error);
+                        searcher.time_manager.inc = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
+                            send!("info string Invalid parameter value given: {}", error);
                             return 0;
                         });
-                        if searcher.inc != 0 { searcher.inc += 6; }
                     }
                 },
                 "movestogo" => {
-                    searcher.movestogo = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
-                        println!("info string Invalid parameter value given: {}", error);
+                    searcher.time_manager.movestogo = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
+                        send!("info string Invalid parameter value given: {}", error);
                         return 0;
                     });
-                    if searcher.movestogo != 0 { searcher.movestogo += 10; }
                 },
                 "movetime" => {
-                    searcher.movetime = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
-                        println!("info string Invalid parameter value given: {}", error);
+                    searcher.time_manager.movetime = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
+                        send!("info string Invalid parameter value given: {}", error);
                         return -1;
                     });
-                    if searcher.movetime != -1 { searcher.movetime += 9; }
+                },
+                "ponder" => {
+                    // search in infinite mode until "ponderhit" switches to the normal time control
+                    searcher.ponder = true;
+                },
+                "infinite" => {
+                    // search until "stop" is received
+                    searcher.infinite = true;
+                },
+                "nodes" => {
+                    searcher.node_limit = split_cmd.next().unwrap_or_else(silent).parse::<u64>().unwrap_or_else(|error| {
+                        send!("info string Invalid parameter value given: {}", error);
+                        0
+                    });
+                },
+                "mate" => {
+                    searcher.mate = split_cmd.next().unwrap_or_else(silent).parse::<u8>().unwrap_or_else(|error| {
+                        send!("info string Invalid parameter value given: {}", error);
+                        0
+                    });
+                },
+                "searchmoves" => {
+                    // restrict the root to the given moves, up to the next "go" parameter
+                    while let Some(move_string) = split_cmd.peek() {
+                        if GO_PARAMETERS.contains(move_string) {
+                            break;
+                        }
+                        let move_ = self.parse_uci(move_string);
+                        if move_ != 0 {
+                            searcher.search_moves.push(move_);
+                        } else {
+                            send!("info string Invalid search move given: {}", move_string);
+                        }
+                        split_cmd.next();
+                    }
                 },
                 _ => ()
             }
         }
 
-        if searcher.movetime != -1 {
-            searcher.playtime = searcher.movetime;
-            searcher.movestogo = 1;
+        // play weaker if the GUI asked for it
+        if let Some(skill) = Skill::from_options() {
+            skill.apply(&mut searcher, self);
         }
 
-        if searcher.playtime != -1 {
-            searcher.timeset = true;
-            searcher.playtime /= searcher.movestogo;
-
-            // calculate the stop time based on the available moves and the phase of the game
-            // let mut move_list = MoveList::new();
-            // self.generate_pseudo_moves(&mut move_list);
-            searcher.stoptime = searcher.time + searcher.playtime as u128 + searcher.inc as u128;
+        searcher.time_manager.init(OPTIONS.move_overhead, OPTIONS.ponder);
+        // infinite searches ignore the clock
+        if searcher.infinite {
+            searcher.time_manager.timeset = false;
         }
         
         if depth == 0 { depth = MAX_PLY as u8; }
-        println!("info string time: {} start: {} stop: {} depth: {} timeset: {}", searcher.playtime, searcher.time, searcher.stoptime, depth, searcher.timeset);
+        send!("info string soft: {} hard: {} depth: {} timeset: {} ponder: {}", searcher.time_manager.soft_limit, searcher.time_manager.hard_limit, depth, searcher.time_manager.timeset, searcher.ponder);
 
-        static mut search: Searcher = Searcher::new();
-        search = searcher.clone();
-        static mut pos: Position = Position::empty();
-        pos = self.clone();
-        search.search_position(&mut pos, depth);
+        searcher.search_position(self, depth);
         }
     }
 }
@@ -193,7 +180,7 @@ impl Position {
 pub fn parse_position(cmd: &str) -> Position {
     // init error closures
     let error = || {
-        println!("info string Invalid uci command given");
+        send!("info string Invalid uci command given");
         return ".";
     };
     let silent = || {
@@ -220,20 +207,26 @@ pub fn parse_position(cmd: &str) -> Position {
         position = Position::new();
     // parse UCI "fen" command
     } else if next == "fen" {
-        // make sure "fen" command is available within command string
-        let fen = split_cmd.next().unwrap_or_else(silent).to_string()+" "+
-        &(split_cmd.next().unwrap_or_else(silent)).to_string()+" "+&(split_cmd.next().unwrap_or_else(silent)).to_string()+" "+
-        &(split_cmd.next().unwrap_or_else(silent)).to_string()+" "+&(split_cmd.next().unwrap_or_else(silent)).to_string()+" "+
-        &(split_cmd.next().unwrap_or_else(silent)).to_string();
-
-        // check if any of the fen assignments failed
-        if fen.ends_with(".") {
-            println!("info string Invalid fen given");
-            return Position::empty();
+        // the fen goes up to the moves, the move counters may be left out
+        let mut fen_fields = Vec::with_capacity(6);
+        while let Some(token) = split_cmd.clone().next() {
+            if token == "moves" {
+                break;
+            }
+            fen_fields.push(token);
+            split_cmd.next();
         }
+        let fen = fen_fields.join(" ");
+
         // init board from fen
-        position = Position::from_fen(&fen);
-    } else if next != "." { println!("info string Invalid uci command given"); }
+        match Position::from_fen_lenient(&fen).and_then(|position| position.validate().map(|_| position)) {
+            Ok(parsed) => position = parsed,
+            Err(error) => {
+                send!("info string Invalid fen given: {}", error);
+                return Position::empty();
+            }
+        }
+    } else if next != "." { send!("info string Invalid uci command given"); }
 
     // parse moves after position
     if position.occupancies[0].0 != 0 {
@@ -245,7 +238,7 @@ pub fn parse_position(cmd: &str) -> Position {
                     position.make(move_);
 
                 } else {
-                    println!("info string Invalid move given");
+                    send!("info string Invalid move given");
                     return Position::empty();
                 }
             }
@@ -260,7 +253,7 @@ pub fn parse_position(cmd: &str) -> Position {
 pub fn parse_option(cmd: &str) {
     // init error closures
     let error = || {
-        println!("info string Invalid option given");
+        send!("info string Invalid option given");
         return ".";
     };
     let silent = || {
@@ -284,18 +277,18 @@ pub fn parse_option(cmd: &str) {
                 } else if response == "false" {
                     unsafe { OPTIONS.threads_automatic = false; }
                 } else {
-                    println!("info string Invalid value given, please give either true or false");
+                    send!("info string Invalid value given, please give either true or false");
                 }
             }
         }
     } else if name == "threads" {
         if split_cmd.next().unwrap_or_else(error) == "value" {
             let value = split_cmd.next().unwrap_or_else(silent).parse::<u16>().unwrap_or_else(|error| {
-                println!("info string Invalid value for option given: {}", error);
+                send!("info string Invalid value for option given: {}", error);
                 return 0;
             });
             if value < 1 {
-                println!("info string Invalid value given, please give a value greater than 0");
+                send!("info string Invalid value given, please give a value greater than 0");
             } else {
                 unsafe { 
                     OPTIONS.threads = value;
@@ -305,11 +298,11 @@ pub fn parse_option(cmd: &str) {
     } else if name == "hash" {
         if split_cmd.next().unwrap_or_else(error) == "value" {
             let value = split_cmd.next().unwrap_or_else(silent).parse::<u16>().unwrap_or_else(|error| {
-                println!("info string Invalid value for option given: {}", error);
+                send!("info string Invalid value for option given: {}", error);
                 return 0;
             });
             if value < 1 {
-                println!("info string Invalid value given, please give a value greater than 0");
+                send!("info string Invalid value given, please give a value greater than 0");
             } else {
                 unsafe { 
                     OPTIONS.hash_size = value; 
@@ -317,19 +310,114 @@ pub fn parse_option(cmd: &str) {
                 }
             }
         }
+    } else if name == "move" {
+        if split_cmd.next().unwrap_or_else(error) == "overhead" {
+            if split_cmd.next().unwrap_or_else(error) == "value" {
+                let value = split_cmd.next().unwrap_or_else(silent).parse::<u16>().unwrap_or_else(|error| {
+                    send!("info string Invalid value for option given: {}", error);
+                    u16::MAX
+                });
+                if value > 5000 {
+                    send!("info string Invalid value given, please give a value between 0 and 5000");
+                } else {
+                    unsafe { OPTIONS.move_overhead = value; }
+                }
+            }
+        }
+    } else if name == "multipv" {
+        if split_cmd.next().unwrap_or_else(error) == "value" {
+            let value = split_cmd.next().unwrap_or_else(silent).parse::<u16>().unwrap_or_else(|error| {
+                send!("info string Invalid value for option given: {}", error);
+                0
+            });
+            if value < 1 || value > 256 {
+                send!("info string Invalid value given, please give a value between 1 and 256");
+            } else {
+                unsafe { OPTIONS.multipv = value; }
+            }
+        }
+    } else if name == "ponder" {
+        if split_cmd.next().unwrap_or_else(error) == "value" {
+            let response = split_cmd.next().unwrap_or_else(error);
+            if response == "true" {
+                unsafe { OPTIONS.ponder = true; }
+            } else if response == "false" {
+                unsafe { OPTIONS.ponder = false; }
+            } else {
+                send!("info string Invalid value given, please give either true or false");
+            }
+        }
+    } else if name == "skill" {
+        if split_cmd.next().unwrap_or_else(error) == "level" && split_cmd.next().unwrap_or_else(error) == "value" {
+            let value = split_cmd.next().unwrap_or_else(silent).parse::<u8>().unwrap_or_else(|error| {
+                send!("info string Invalid value for option given: {}", error);
+                u8::MAX
+            });
+            if value > MAX_SKILL_LEVEL {
+                send!("info string Invalid value given, please give a value between 0 and {}", MAX_SKILL_LEVEL);
+            } else {
+                unsafe { OPTIONS.skill_level = value; }
+            }
+        }
+    } else if name == "uci_limitstrength" {
+        if split_cmd.next().unwrap_or_else(error) == "value" {
+            let response = split_cmd.next().unwrap_or_else(error);
+            if response == "true" {
+                unsafe { OPTIONS.limit_strength = true; }
+            } else if response == "false" {
+                unsafe { OPTIONS.limit_strength = false; }
+            } else {
+                send!("info string Invalid value given, please give either true or false");
+            }
+        }
+    } else if name == "uci_elo" {
+        if split_cmd.next().unwrap_or_else(error) == "value" {
+            let value = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
+                send!("info string Invalid value for option given: {}", error);
+                i32::MIN
+            });
+            if value < Skill::min_elo() || value > Skill::max_elo() {
+                send!("info string Invalid value given, please give a value between {} and {}", Skill::min_elo(), Skill::max_elo());
+            } else {
+                unsafe { OPTIONS.elo = value as u16; }
+            }
+        }
     } else if name == "clear" {
         if split_cmd.next().unwrap_or_else(error) == "hash" {
-            unsafe { TT.reset(); }
+            unsafe { (*addr_of_mut!(TT)).reset(); }
         }
     } else if name == "uci_variant" {
         if split_cmd.next().unwrap_or_else(error) == "value" {
             // check if variant in SUPPORTED_VARIANTS list
             let variant = split_cmd.next().unwrap_or_else(error);
             if !SUPPORTED_VARIANTS.contains(&variant) {
-                println!("info string Unknown variant given");
+                send!("info string Unknown variant given");
+            }
+        }
+    } else if name == "debug" {
+        if split_cmd.next().unwrap_or_else(error) == "log" && split_cmd.next().unwrap_or_else(error) == "file" {
+            if split_cmd.next().unwrap_or_else(error) == "value" {
+                // take the path from the original command, file names are case sensitive
+                let words: Vec<&str> = cmd.split_whitespace().collect();
+                let value = words.iter().position(|word| word.eq_ignore_ascii_case("value")).map(|index| words[index + 1..].join(" ")).unwrap_or_default();
+                if let Err(message) = set_log_file(&value) {
+                    send!("info string {}", message);
+                }
+            }
+        }
+    } else if let Some(tunable) = find_tunable(name) {
+        if split_cmd.next().unwrap_or_else(error) == "value" {
+            let value = split_cmd.next().unwrap_or_else(silent).parse::<i32>().unwrap_or_else(|error| {
+                send!("info string Invalid value for option given: {}", error);
+                i32::MIN
+            });
+            if value < tunable.min || value > tunable.max {
+                send!("info string Invalid value given, please give a value between {} and {}", tunable.min, tunable.max);
+            } else {
+                tunable.set(value);
             }
         }
     } else {
-        println!("info string Unknown option given");
+        send!("info string Unknown option given");
     }
 }
\ No newline at end of file